@@ -0,0 +1,72 @@
+// Dream distortion for sprites: wobble, chromatic offset, hue shift and dithering,
+// all scaled by `intensity`.
+
+#import bevy_sprite::{
+    mesh2d_vertex_output::VertexOutput,
+    mesh2d_view_bindings::globals,
+}
+
+struct DreamParams {
+    color: vec4<f32>,
+    // The frame within the texture: min uv in xy, max uv in zw. Swapped to flip.
+    uv_rect: vec4<f32>,
+    intensity: f32,
+    seed: f32,
+}
+
+@group(2) @binding(0) var<uniform> params: DreamParams;
+@group(2) @binding(1) var sprite_texture: texture_2d<f32>;
+@group(2) @binding(2) var sprite_sampler: sampler;
+
+// Rotate a color around the grey axis.
+fn hue_shift(color: vec3<f32>, angle: f32) -> vec3<f32> {
+    let k = vec3<f32>(0.57735);
+    let c = cos(angle);
+    let s = sin(angle);
+    return color * c + cross(k, color) * s + k * dot(k, color) * (1.0 - c);
+}
+
+// 4x4 ordered dithering threshold between 0 and 1.
+fn bayer(position: vec2<f32>) -> f32 {
+    var matrix = array<f32, 16>(
+        0.0, 8.0, 2.0, 10.0,
+        12.0, 4.0, 14.0, 6.0,
+        3.0, 11.0, 1.0, 9.0,
+        15.0, 7.0, 13.0, 5.0,
+    );
+    let cell = vec2<u32>(position) % vec2<u32>(4u);
+    return (matrix[cell.y * 4u + cell.x] + 0.5) / 16.0;
+}
+
+fn sample_frame(uv: vec2<f32>) -> vec4<f32> {
+    let lo = min(params.uv_rect.xy, params.uv_rect.zw);
+    let hi = max(params.uv_rect.xy, params.uv_rect.zw);
+    let frame_uv = mix(params.uv_rect.xy, params.uv_rect.zw, clamp(uv, vec2(0.0), vec2(1.0)));
+    // Don't bleed into neighbouring frames of the atlas.
+    return textureSample(sprite_texture, sprite_sampler, clamp(frame_uv, lo, hi));
+}
+
+@fragment
+fn fragment(mesh: VertexOutput) -> @location(0) vec4<f32> {
+    let i = params.intensity;
+    let t = globals.time + params.seed;
+
+    var uv = mesh.uv;
+    uv.x += sin(uv.y * 12.0 + t * 2.0) * 0.03 * i;
+    uv.y += cos(uv.x * 9.0 + t * 1.7) * 0.015 * i;
+
+    let offset = vec2<f32>(0.04 * i, 0.0);
+    let red = sample_frame(uv + offset);
+    let base = sample_frame(uv);
+    let blue = sample_frame(uv - offset);
+    var color = vec4<f32>(red.r, base.g, blue.b, max(base.a, max(red.a, blue.a)));
+
+    color = vec4<f32>(hue_shift(color.rgb, i * 1.5 * sin(t * 0.3)), color.a);
+
+    // Fewer color levels the deeper the dream.
+    let levels = mix(64.0, 4.0, i);
+    let threshold = bayer(mesh.position.xy) - 0.5;
+    color = vec4<f32>(floor(color.rgb * levels + threshold + 0.5) / levels, color.a);
+
+    return color * params.color;
+}
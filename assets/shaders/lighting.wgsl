@@ -0,0 +1,42 @@
+// Darkness over the world, lifted around point lights.
+// Each light lifts the darkness by its intensity at its center, fading out to its radius,
+// and tints what it reveals a little with its color.
+
+#import bevy_sprite::mesh2d_vertex_output::VertexOutput
+
+// Must match `MAX_LIGHTS` in `lighting.rs`.
+const MAX_LIGHTS: u32 = 64u;
+
+struct LightingParams {
+    // The color of the darkness in rgb, and the ambient level in a.
+    ambient: vec4<f32>,
+    // Position in xy, radius in z and falloff in w, in world units.
+    lights: array<vec4<f32>, 64>,
+    // Color in rgb and intensity in a.
+    colors: array<vec4<f32>, 64>,
+    count: u32,
+}
+
+@group(2) @binding(0) var<uniform> params: LightingParams;
+
+@fragment
+fn fragment(mesh: VertexOutput) -> @location(0) vec4<f32> {
+    var total = 0.0;
+    var tint = vec3<f32>(0.0);
+    for (var i = 0u; i < min(params.count, MAX_LIGHTS); i++) {
+        let light = params.lights[i];
+        let distance = length(mesh.world_position.xy - light.xy);
+        let reach = pow(clamp(1.0 - distance / light.z, 0.0, 1.0), light.w);
+        let amount = reach * params.colors[i].a;
+        total += amount;
+        tint += params.colors[i].rgb * amount;
+    }
+    let lit = clamp(total, 0.0, 1.0);
+    // Overlapping lights add up, but their tint is the blend of their colors.
+    tint = tint / max(total, 0.0001);
+
+    let darkness = params.ambient.a * (1.0 - lit);
+    // Where light reaches, darken toward its color instead of black.
+    let color = mix(params.ambient.rgb, tint, lit);
+    return vec4<f32>(color, max(darkness, lit * params.ambient.a * 0.15));
+}
@@ -0,0 +1,83 @@
+// Full-screen effects on the canvas: CRT curvature, a warp pulse, bloom,
+// vignette and film grain. An effect with no strength leaves the image alone.
+
+#import bevy_sprite::{
+    mesh2d_vertex_output::VertexOutput,
+    mesh2d_view_bindings::globals,
+}
+
+struct PostProcessParams {
+    vignette_strength: f32,
+    vignette_radius: f32,
+    grain: f32,
+    curvature: f32,
+    bloom_threshold: f32,
+    bloom_intensity: f32,
+    warp: f32,
+    // WebGL2 needs uniforms to be a multiple of 16 bytes.
+    _webgl2_padding: f32,
+}
+
+@group(2) @binding(0) var<uniform> params: PostProcessParams;
+@group(2) @binding(1) var canvas_texture: texture_2d<f32>;
+@group(2) @binding(2) var canvas_sampler: sampler;
+
+// Push uvs outward the further they are from the center.
+fn curve(uv: vec2<f32>) -> vec2<f32> {
+    let centered = uv * 2.0 - 1.0;
+    let bent = centered * (1.0 + params.curvature * dot(centered, centered));
+    return bent * 0.5 + 0.5;
+}
+
+// A ring rippling out from the center.
+fn warp(uv: vec2<f32>) -> vec2<f32> {
+    let centered = uv - 0.5;
+    let distance = length(centered);
+    let ripple = sin(distance * 30.0 - globals.time * 12.0);
+    return uv + normalize(centered + vec2(1e-5)) * ripple * params.warp;
+}
+
+fn bright(uv: vec2<f32>) -> vec3<f32> {
+    let color = textureSample(canvas_texture, canvas_sampler, uv).rgb;
+    return max(color - vec3(params.bloom_threshold), vec3(0.0));
+}
+
+// Average the bright parts of two rings of neighbours.
+fn bloom(uv: vec2<f32>) -> vec3<f32> {
+    let texel = 1.0 / vec2<f32>(textureDimensions(canvas_texture));
+    var sum = vec3(0.0);
+    for (var i = 0; i < 8; i++) {
+        let angle = f32(i) * 0.785398;
+        let direction = vec2(cos(angle), sin(angle)) * texel;
+        sum += bright(uv + direction * 1.5);
+        sum += bright(uv + direction * 3.5) * 0.5;
+    }
+    return sum / 12.0;
+}
+
+fn hash(position: vec2<f32>) -> f32 {
+    return fract(sin(dot(position, vec2(12.9898, 78.233))) * 43758.5453);
+}
+
+@fragment
+fn fragment(mesh: VertexOutput) -> @location(0) vec4<f32> {
+    let uv = warp(curve(mesh.uv));
+    // Beyond the bent edges of the screen.
+    if any(uv < vec2(0.0)) || any(uv > vec2(1.0)) {
+        return vec4(0.0, 0.0, 0.0, 1.0);
+    }
+
+    var color = textureSample(canvas_texture, canvas_sampler, uv).rgb;
+    if params.bloom_intensity > 0.0 {
+        color += bloom(uv) * params.bloom_intensity;
+    }
+
+    let distance = length(mesh.uv - 0.5) * 1.41421;
+    let shade = smoothstep(params.vignette_radius, 1.0, distance) * params.vignette_strength;
+    color *= 1.0 - shade;
+
+    let noise = hash(mesh.position.xy + fract(globals.time) * 100.0) - 0.5;
+    color += noise * params.grain;
+
+    return vec4(clamp(color, vec3(0.0), vec3(1.0)), 1.0);
+}
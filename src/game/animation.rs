@@ -1,232 +1,397 @@
-//! Player sprite animation.
+//! Sprite animation driven by [`Aseprite`] sheets.
+//! An [`AnimationController`] is a small state machine whose states are the sheet's tags.
+//! It switches between them when its parameters meet a [`Condition`],
+//! and triggers [`AnimationEvent`]s on the entity when marked frames come up.
+//! Entities with a [`Facing`] play the variant of each state for their direction,
+//! e.g. `walk_up`, falling back to a nearby direction or the plain tag when the sheet lacks it.
 //! This is based on multiple examples and may be very different for your game.
 //! - [Sprite flipping](https://github.com/bevyengine/bevy/blob/latest/examples/2d/sprite_flipping.rs)
 //! - [Sprite animation](https://github.com/bevyengine/bevy/blob/latest/examples/2d/sprite_animation.rs)
-//! - [Timers](https://github.com/bevyengine/bevy/blob/latest/examples/time/timers.rs)
 
 use std::time::Duration;
 
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::HashMap};
 
-use super::{audio::sfx::PlaySfx, movement::MovementController};
+use super::{aseprite::Aseprite, audio::sfx::PlaySfx, movement::MovementController};
 use crate::AppSet;
 
 pub(super) fn plugin(app: &mut App) {
     // Animate and play sound effects based on controls.
-    app.register_type::<PlayerAnimation>();
-    app.register_type::<BasicAnimation>();
+    app.register_type::<(AnimationController, Facing)>();
+    app.observe(insert_animation_atlas)
+        .observe(play_footstep_sfx);
     app.add_systems(
         Update,
-        (
-            update_animation_timer.in_set(AppSet::TickTimers),
-            (
-                update_animation_movement,
-                update_animation_atlas,
-                trigger_step_sfx,
-            )
-                .chain()
-                .in_set(AppSet::Update),
-        ),
+        (update_animation_movement, update_animation_controllers)
+            .chain()
+            .in_set(AppSet::Update),
     );
 }
 
-/// Update the sprite direction and animation state (idling/walking).
-fn update_animation_movement(
-    mut player_query: Query<(&MovementController, &mut Sprite, &mut PlayerAnimation)>,
-) {
-    for (controller, mut sprite, mut animation) in &mut player_query {
-        let dx = controller.0.x;
-        if dx != 0.0 {
-            sprite.flip_x = dx < 0.0;
-        }
-
-        let animation_state = if controller.0 == Vec2::ZERO {
-            PlayerAnimationState::Idling
-        } else {
-            PlayerAnimationState::Walking
-        };
-        animation.update_state(animation_state);
-    }
+/// When an [`AnimationController`] switches from one state to another.
+#[derive(Reflect, Debug, Clone, PartialEq)]
+pub enum Condition {
+    /// The parameter is greater than the value.
+    Above(String, f32),
+    /// The parameter is at most the value.
+    AtMost(String, f32),
+    /// The current state played through once.
+    Finished,
 }
 
-/// Update the animation timer.
-fn update_animation_timer(
-    time: Res<Time>,
-    mut query0: Query<&mut PlayerAnimation>,
-    mut query1: Query<&mut BasicAnimation>,
-) {
-    for mut player_animation in &mut query0 {
-        player_animation.update_timer(time.delta());
-    }
-
-    for mut basic_animation in &mut query1 {
-        // println!(
-        //     "Before update: Timer: {:?}, Frame: {}",
-        //     basic_animation.timer.elapsed(),
-        //     basic_animation.frame
-        // ); // Debugging statement
-        basic_animation.update_timer(time.delta());
-        // println!(
-        //     "After update: Timer: {:?}, Frame: {}",
-        //     basic_animation.timer.elapsed(),
-        //     basic_animation.frame
-        // ); // Debugging statement
-    }
+#[derive(Reflect, Debug, Clone)]
+struct Transition {
+    /// `None` transitions from any state.
+    from: Option<String>,
+    to: String,
+    condition: Condition,
 }
 
-fn update_animation_atlas(
-    mut animations: ParamSet<(
-        Query<(&PlayerAnimation, &mut TextureAtlas)>,
-        Query<(&BasicAnimation, &mut TextureAtlas)>,
-    )>,
-) {
-    {
-        let mut query0 = animations.p0();
-        for (animation, mut atlas) in query0.iter_mut() {
-            if animation.changed() {
-                atlas.index = animation.get_atlas_index();
-            }
-        }
-    }
-    {
-        let mut query1 = animations.p1();
-        for (animation, mut atlas) in query1.iter_mut() {
-            if animation.changed() {
-                atlas.index = animation.get_atlas_index();
-            }
-        }
-    }
+#[derive(Reflect, Debug, Clone)]
+struct FrameEvent {
+    state: String,
+    step: usize,
+    name: String,
 }
 
-/// If the player is moving, play a step sound effect synchronized with the animation.
-fn trigger_step_sfx(mut commands: Commands, mut step_query: Query<&PlayerAnimation>) {
-    for animation in &mut step_query {
-        if animation.state == PlayerAnimationState::Walking
-            && animation.changed()
-            && (animation.frame == 2 || animation.frame == 5)
-        {
-            commands.trigger(PlaySfx::RandomStep);
-        }
-    }
-}
+/// Triggered on an animated entity when a frame marked with
+/// [`AnimationController::with_event`] comes up.
+#[derive(Event, Debug, Clone)]
+pub struct AnimationEvent(pub String);
 
-/// Component that tracks player's animation state.
-/// It is tightly bound to the texture atlas we use.
+/// Plays the tags of an [`Aseprite`] sheet as states of a state machine.
 #[derive(Component, Reflect)]
 #[reflect(Component)]
-pub struct PlayerAnimation {
-    timer: Timer,
-    frame: usize,
-    state: PlayerAnimationState,
-}
-#[derive(Component, Reflect)]
-#[reflect(Component)]
-pub struct BasicAnimation {
-    timer: Timer,
-    frame: usize,
-    num_frames: usize,
+pub struct AnimationController {
+    aseprite: Handle<Aseprite>,
+    transitions: Vec<Transition>,
+    events: Vec<FrameEvent>,
+    params: HashMap<String, f32>,
+    /// The state being played.
+    state: String,
+    /// The sheet tag shown for the state, which depends on [`Facing`].
+    tag: String,
+    /// The tags already resolved for each state and facing.
+    #[reflect(ignore)]
+    resolved_tags: HashMap<String, HashMap<Option<Facing>, Option<String>>>,
+    /// Position within the tag.
+    step: usize,
+    elapsed: Duration,
+    changed: bool,
+    finished: bool,
 }
 
-#[derive(Reflect, PartialEq)]
-pub enum PlayerAnimationState {
-    Idling,
-    Walking,
-}
-impl BasicAnimation {
-    /// The duration of each idle frame.
-    const INTERVAL: Duration = Duration::from_millis(500);
+impl AnimationController {
+    /// Start out playing the `initial` tag in a loop.
+    pub fn new(aseprite: Handle<Aseprite>, initial: impl Into<String>) -> Self {
+        let state = initial.into();
+        Self {
+            aseprite,
+            transitions: Vec::new(),
+            events: Vec::new(),
+            params: HashMap::new(),
+            tag: state.clone(),
+            resolved_tags: HashMap::new(),
+            state,
+            step: 0,
+            elapsed: Duration::ZERO,
+            changed: true,
+            finished: false,
+        }
+    }
 
-    pub fn new(num_frames: usize) -> Self {
-        Self::idling(num_frames)
+    /// Switch from the `from` state, or from any state if `None`, to `to` when `condition` holds.
+    /// Transitions are checked in the order they were added.
+    pub fn with_transition(
+        mut self,
+        from: Option<&str>,
+        to: impl Into<String>,
+        condition: Condition,
+    ) -> Self {
+        self.transitions.push(Transition {
+            from: from.map(str::to_string),
+            to: to.into(),
+            condition,
+        });
+        self
     }
 
-    fn idling(num_frames: usize) -> Self {
-        Self {
-            timer: Timer::new(Self::INTERVAL, TimerMode::Repeating),
-            frame: 0,
-            num_frames,
+    /// Trigger an [`AnimationEvent`] named `name` when `state` reaches its `step`th frame.
+    pub fn with_event(
+        mut self,
+        state: impl Into<String>,
+        step: usize,
+        name: impl Into<String>,
+    ) -> Self {
+        self.events.push(FrameEvent {
+            state: state.into(),
+            step,
+            name: name.into(),
+        });
+        self
+    }
+
+    /// Set a parameter that transitions read. Unset parameters count as 0.
+    pub fn set_param(&mut self, name: &str, value: f32) {
+        match self.params.get_mut(name) {
+            Some(param) => *param = value,
+            None => {
+                self.params.insert(name.to_string(), value);
+            }
         }
     }
-    pub fn update_timer(&mut self, delta: Duration) {
-        self.timer.tick(delta);
-        if self.timer.finished() {
-            self.frame = (self.frame + 1) % self.num_frames;
+
+    pub fn param(&self, name: &str) -> f32 {
+        self.params.get(name).copied().unwrap_or_default()
+    }
+
+    /// Switch to another state right away, restarting it. Playing the current state does nothing.
+    pub fn play(&mut self, state: &str) {
+        if self.state != state {
+            self.state = state.to_string();
+            self.step = 0;
+            self.elapsed = Duration::ZERO;
+            self.changed = true;
+            self.finished = false;
         }
     }
-    /// Return sprite index in the atlas.
-    pub fn get_atlas_index(&self) -> usize {
-        self.frame
+
+    fn holds(&self, condition: &Condition) -> bool {
+        match condition {
+            Condition::Above(param, value) => self.param(param) > *value,
+            Condition::AtMost(param, value) => self.param(param) <= *value,
+            Condition::Finished => self.finished,
+        }
     }
-    /// Whether animation changed this tick.
-    pub fn changed(&self) -> bool {
-        self.timer.finished()
+
+    fn next_state(&self) -> Option<String> {
+        self.transitions
+            .iter()
+            .filter(|transition| {
+                transition.to != self.state
+                    && !matches!(&transition.from, Some(from) if *from != self.state)
+            })
+            .find(|transition| self.holds(&transition.condition))
+            .map(|transition| transition.to.clone())
+    }
+
+    fn events_at(&self, step: usize) -> impl Iterator<Item = &str> {
+        self.events
+            .iter()
+            .filter(move |event| event.state == self.state && event.step == step)
+            .map(|event| event.name.as_str())
     }
 }
 
-impl PlayerAnimation {
-    /// The number of idle frames.
-    const IDLE_FRAMES: usize = 2;
-    /// The duration of each idle frame.
-    const IDLE_INTERVAL: Duration = Duration::from_millis(500);
+/// Which way a character looks, in eight directions.
+/// Side-facing frames are drawn facing right and flipped for the left.
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[reflect(Component)]
+pub enum Facing {
+    Up,
+    Down,
+    Left,
+    #[default]
+    Right,
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+}
 
-    fn idling() -> Self {
-        Self {
-            timer: Timer::new(Self::IDLE_INTERVAL, TimerMode::Repeating),
-            frame: 0,
-            state: PlayerAnimationState::Idling,
+impl Facing {
+    /// The nearest of the eight directions, or `None` for no direction at all.
+    pub fn from_direction(direction: Vec2) -> Option<Self> {
+        if direction == Vec2::ZERO {
+            return None;
         }
+        let octant = (direction.to_angle() / std::f32::consts::FRAC_PI_4).round() as i32;
+        Some(match octant.rem_euclid(8) {
+            0 => Facing::Right,
+            1 => Facing::UpRight,
+            2 => Facing::Up,
+            3 => Facing::UpLeft,
+            4 => Facing::Left,
+            5 => Facing::DownLeft,
+            6 => Facing::Down,
+            _ => Facing::DownRight,
+        })
     }
 
-    /// The number of walking frames.
-    const WALKING_FRAMES: usize = 6;
-    /// The duration of each walking frame.
-    const WALKING_INTERVAL: Duration = Duration::from_millis(50);
+    /// Tag suffixes to try for this direction, best first.
+    /// The plain tag is the last resort after all of them.
+    fn suffixes(self) -> &'static [&'static str] {
+        match self {
+            Facing::Up => &["_up"],
+            Facing::Down => &["_down"],
+            Facing::Left | Facing::Right => &["_side"],
+            Facing::UpLeft | Facing::UpRight => &["_up_side", "_side", "_up"],
+            Facing::DownLeft | Facing::DownRight => &["_down_side", "_side", "_down"],
+        }
+    }
 
-    fn walking() -> Self {
-        Self {
-            timer: Timer::new(Self::WALKING_INTERVAL, TimerMode::Repeating),
-            frame: 0,
-            state: PlayerAnimationState::Walking,
+    /// Whether the direction has a horizontal part, and if so, whether it is to the left.
+    fn is_left(self) -> Option<bool> {
+        match self {
+            Facing::Up | Facing::Down => None,
+            Facing::Left | Facing::UpLeft | Facing::DownLeft => Some(true),
+            Facing::Right | Facing::UpRight | Facing::DownRight => Some(false),
         }
     }
+}
 
-    pub fn new() -> Self {
-        Self::idling()
+/// The tag of the sheet that shows `state` best for the facing.
+/// Looked up once per state and facing, then remembered in `resolved_tags`.
+fn resolve_tag<'a>(
+    resolved_tags: &'a mut HashMap<String, HashMap<Option<Facing>, Option<String>>>,
+    aseprite: &Aseprite,
+    state: &str,
+    facing: Option<Facing>,
+) -> Option<&'a String> {
+    if !resolved_tags.contains_key(state) {
+        resolved_tags.insert(state.to_string(), HashMap::new());
     }
+    resolved_tags
+        .get_mut(state)?
+        .entry(facing)
+        .or_insert_with(|| {
+            let suffixes = facing.map_or(&[][..], Facing::suffixes);
+            suffixes
+                .iter()
+                .map(|suffix| format!("{state}{suffix}"))
+                .chain(std::iter::once(state.to_string()))
+                .find(|tag| aseprite.tag(tag).is_some())
+        })
+        .as_ref()
+}
 
-    /// Update animation timers.
-    pub fn update_timer(&mut self, delta: Duration) {
-        self.timer.tick(delta);
-        if !self.timer.finished() {
-            return;
-        }
-        self.frame = (self.frame + 1)
-            % match self.state {
-                PlayerAnimationState::Idling => Self::IDLE_FRAMES,
-                PlayerAnimationState::Walking => Self::WALKING_FRAMES,
-            };
+/// Give new animations the atlas of their sheet right away, so their first frame is correct.
+fn insert_animation_atlas(
+    trigger: Trigger<OnAdd, AnimationController>,
+    mut commands: Commands,
+    aseprites: Res<Assets<Aseprite>>,
+    mut animation_query: Query<(&mut AnimationController, Option<&Facing>)>,
+) {
+    let entity = trigger.entity();
+    let Ok((mut animation, facing)) = animation_query.get_mut(entity) else {
+        return;
+    };
+    let Some(aseprite) = aseprites.get(&animation.aseprite) else {
+        return;
+    };
+    let animation = animation.as_mut();
+    if let Some(tag) = resolve_tag(
+        &mut animation.resolved_tags,
+        aseprite,
+        &animation.state,
+        facing.copied(),
+    ) {
+        commands.entity(entity).insert(aseprite.atlas(tag));
+        animation.tag.clone_from(tag);
     }
+}
 
-    /// Update animation state if it changes.
-    pub fn update_state(&mut self, state: PlayerAnimationState) {
-        if self.state != state {
-            match state {
-                PlayerAnimationState::Idling => *self = Self::idling(),
-                PlayerAnimationState::Walking => *self = Self::walking(),
+/// Update the facing, sprite direction and the speed the animation reacts to.
+/// Facing is kept while standing still.
+fn update_animation_movement(
+    mut player_query: Query<(
+        &MovementController,
+        &mut Sprite,
+        &mut AnimationController,
+        Option<&mut Facing>,
+    )>,
+) {
+    for (controller, mut sprite, mut animation, facing) in &mut player_query {
+        if let Some(new_facing) = Facing::from_direction(controller.0) {
+            if let Some(is_left) = new_facing.is_left() {
+                sprite.flip_x = is_left;
+            }
+            if let Some(mut facing) = facing {
+                facing.set_if_neq(new_facing);
             }
         }
+        animation.set_param("speed", controller.0.length());
     }
+}
 
-    /// Whether animation changed this tick.
-    pub fn changed(&self) -> bool {
-        self.timer.finished()
-    }
+/// Follow transitions, advance frames by their durations, and trigger frame events.
+fn update_animation_controllers(
+    mut commands: Commands,
+    time: Res<Time>,
+    aseprites: Res<Assets<Aseprite>>,
+    mut animation_query: Query<(
+        Entity,
+        &mut AnimationController,
+        &mut TextureAtlas,
+        Option<&Facing>,
+    )>,
+) {
+    for (entity, mut animation, mut atlas, facing) in &mut animation_query {
+        let animation = animation.as_mut();
+        let Some(aseprite) = aseprites.get(&animation.aseprite) else {
+            continue;
+        };
+        if let Some(state) = animation.next_state() {
+            animation.play(&state);
+        }
+        let Some(tag) = resolve_tag(
+            &mut animation.resolved_tags,
+            aseprite,
+            &animation.state,
+            facing.copied(),
+        ) else {
+            warn_once!("Unknown animation tag {:?}", animation.state);
+            continue;
+        };
+        let frames = aseprite.tag(tag).unwrap_or_default();
+        // A state that just started shows its first frame now, but turning doesn't.
+        let started = animation.changed;
+        // Turning mid-stride keeps the stride going in the new direction.
+        if animation.tag != *tag {
+            animation.tag.clone_from(tag);
+            animation.step %= frames.len();
+            animation.changed = true;
+        }
+
+        let mut changed = std::mem::take(&mut animation.changed);
+        // Every frame that came up, even the ones a long update skipped past.
+        let mut steps = Vec::new();
+        if started {
+            steps.push(animation.step);
+        }
+        animation.finished = false;
+        animation.elapsed += time.delta();
+        loop {
+            // Never get stuck on frames without a duration.
+            let duration = aseprite
+                .duration(frames[animation.step])
+                .max(Duration::from_millis(1));
+            if animation.elapsed < duration {
+                break;
+            }
+            animation.elapsed -= duration;
+            animation.step = (animation.step + 1) % frames.len();
+            animation.finished |= animation.step == 0;
+            steps.push(animation.step);
+            changed = true;
+        }
 
-    /// Return sprite index in the atlas.
-    pub fn get_atlas_index(&self) -> usize {
-        match self.state {
-            PlayerAnimationState::Idling => self.frame,
-            PlayerAnimationState::Walking => 6 + self.frame,
+        if changed {
+            atlas.index = frames[animation.step];
+            if atlas.layout != *aseprite.layout() {
+                atlas.layout = aseprite.layout().clone();
+            }
         }
+        for step in steps {
+            for name in animation.events_at(step) {
+                commands.trigger_targets(AnimationEvent(name.to_string()), entity);
+            }
+        }
+    }
+}
+
+fn play_footstep_sfx(trigger: Trigger<AnimationEvent>, mut commands: Commands) {
+    if trigger.event().0 == "footstep" {
+        commands.trigger(PlaySfx::RandomStep);
     }
 }
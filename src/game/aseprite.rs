@@ -0,0 +1,214 @@
+//! Sprite sheets exported from Aseprite.
+//! Export with "Array" frames and "Tags" enabled next to the image, named `<sheet>.aseprite.json`.
+//! Each export becomes an [`Aseprite`] asset with its own [`TextureAtlasLayout`],
+//! so frames can be added, retimed or regrouped into tags without touching code.
+
+use std::{fmt, time::Duration};
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+    utils::HashMap,
+};
+use serde::Deserialize;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_asset::<Aseprite>();
+    app.init_asset_loader::<AsepriteLoader>();
+}
+
+/// A sprite sheet with per-frame durations and named tags.
+#[derive(Asset, TypePath, Debug)]
+pub struct Aseprite {
+    /// How long each frame of the sheet is shown, in atlas order.
+    durations: Vec<Duration>,
+    /// The atlas indices each tag plays, in order, with its direction already applied.
+    tags: HashMap<String, Vec<usize>>,
+    layout: Handle<TextureAtlasLayout>,
+}
+
+impl Aseprite {
+    /// The atlas indices the tag plays through, or `None` if there is no such tag.
+    pub fn tag(&self, name: &str) -> Option<&[usize]> {
+        self.tags.get(name).map(Vec::as_slice)
+    }
+
+    /// How long the frame at the given atlas index is shown.
+    pub fn duration(&self, index: usize) -> Duration {
+        self.durations.get(index).copied().unwrap_or_default()
+    }
+
+    /// An atlas showing the first frame of the tag.
+    pub fn atlas(&self, tag: &str) -> TextureAtlas {
+        TextureAtlas {
+            layout: self.layout.clone(),
+            index: self
+                .tag(tag)
+                .and_then(|frames| frames.first())
+                .copied()
+                .unwrap_or(0),
+        }
+    }
+
+    pub fn layout(&self) -> &Handle<TextureAtlasLayout> {
+        &self.layout
+    }
+}
+
+/// The parts of an Aseprite JSON export we use.
+#[derive(Deserialize)]
+struct AsepriteJson {
+    frames: Vec<FrameJson>,
+    meta: MetaJson,
+}
+
+#[derive(Deserialize)]
+struct FrameJson {
+    frame: RectJson,
+    /// Milliseconds.
+    duration: u64,
+}
+
+#[derive(Deserialize)]
+struct RectJson {
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+}
+
+#[derive(Deserialize)]
+struct SizeJson {
+    w: u32,
+    h: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MetaJson {
+    size: SizeJson,
+    #[serde(default)]
+    frame_tags: Vec<TagJson>,
+}
+
+#[derive(Deserialize)]
+struct TagJson {
+    name: String,
+    from: usize,
+    to: usize,
+    #[serde(default)]
+    direction: TagDirection,
+}
+
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+enum TagDirection {
+    #[default]
+    Forward,
+    Reverse,
+    Pingpong,
+    PingpongReverse,
+}
+
+impl TagJson {
+    /// The frames the tag plays through in one loop.
+    fn frames(&self) -> Vec<usize> {
+        let forward: Vec<_> = (self.from..=self.to).collect();
+        let reverse: Vec<_> = forward.iter().rev().copied().collect();
+        // Ping-pong doesn't repeat the frames it turns around on.
+        let bounce = |there: &[usize], back: &[usize]| {
+            let back = back
+                .get(1..back.len().saturating_sub(1))
+                .unwrap_or_default();
+            there.iter().chain(back).copied().collect()
+        };
+        match self.direction {
+            TagDirection::Forward => forward,
+            TagDirection::Reverse => reverse,
+            TagDirection::Pingpong => bounce(&forward, &reverse),
+            TagDirection::PingpongReverse => bounce(&reverse, &forward),
+        }
+    }
+}
+
+#[derive(Default)]
+struct AsepriteLoader;
+
+impl AssetLoader for AsepriteLoader {
+    type Asset = Aseprite;
+    type Settings = ();
+    type Error = AsepriteError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Aseprite, AsepriteError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let json: AsepriteJson = serde_json::from_slice(&bytes)?;
+
+        let mut layout =
+            TextureAtlasLayout::new_empty(UVec2::new(json.meta.size.w, json.meta.size.h));
+        let mut durations = Vec::with_capacity(json.frames.len());
+        for FrameJson { frame, duration } in &json.frames {
+            layout.add_texture(URect::new(
+                frame.x,
+                frame.y,
+                frame.x + frame.w,
+                frame.y + frame.h,
+            ));
+            durations.push(Duration::from_millis(*duration));
+        }
+
+        let mut tags = HashMap::new();
+        for tag in &json.meta.frame_tags {
+            if tag.from > tag.to || tag.to >= json.frames.len() {
+                return Err(AsepriteError::InvalidTag(tag.name.clone()));
+            }
+            tags.insert(tag.name.clone(), tag.frames());
+        }
+
+        Ok(Aseprite {
+            durations,
+            tags,
+            layout: load_context.add_labeled_asset("layout".to_string(), layout),
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["aseprite.json"]
+    }
+}
+
+#[derive(Debug)]
+pub enum AsepriteError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    InvalidTag(String),
+}
+
+impl fmt::Display for AsepriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AsepriteError::Io(error) => write!(f, "could not read Aseprite export: {error}"),
+            AsepriteError::Json(error) => write!(f, "invalid Aseprite export: {error}"),
+            AsepriteError::InvalidTag(name) => write!(f, "tag {name:?} is out of frame range"),
+        }
+    }
+}
+
+impl std::error::Error for AsepriteError {}
+
+impl From<std::io::Error> for AsepriteError {
+    fn from(error: std::io::Error) -> Self {
+        AsepriteError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for AsepriteError {
+    fn from(error: serde_json::Error) -> Self {
+        AsepriteError::Json(error)
+    }
+}
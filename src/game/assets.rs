@@ -4,9 +4,14 @@ use bevy::{
     utils::HashMap,
 };
 
+use super::{aseprite::Aseprite, tiled::RoomLayout};
+
 pub(super) fn plugin(app: &mut App) {
     app.register_type::<HandleMap<ImageKey>>();
     app.init_resource::<HandleMap<ImageKey>>();
+    // Built from the images above.
+    app.register_type::<AtlasLayouts>();
+    app.init_resource::<AtlasLayouts>();
 
     app.register_type::<HandleMap<SfxKey>>();
     app.init_resource::<HandleMap<SfxKey>>();
@@ -16,6 +21,12 @@ pub(super) fn plugin(app: &mut App) {
 
     app.register_type::<HandleMap<FontKey>>();
     app.init_resource::<HandleMap<FontKey>>();
+
+    app.register_type::<HandleMap<AsepriteKey>>();
+    app.init_resource::<HandleMap<AsepriteKey>>();
+
+    app.register_type::<RoomLayouts>();
+    app.init_resource::<RoomLayouts>();
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Reflect)]
@@ -33,7 +44,7 @@ impl FromWorld for HandleMap<FontKey> {
     }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Hash, Reflect)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Reflect, Debug)]
 pub enum ImageKey {
     Player,
     Npc1,
@@ -50,12 +61,95 @@ pub enum ImageKey {
     Elements2,
     PopUp,
     TitleImage,
+    /// A left and a right eye, open and closed, for things that watch the player.
+    Eyes,
+    /// Room tiles for generated rooms. Tiled maps load the same image themselves.
+    Tiles,
+    /// Seamlessly repeating background layers, one canvas in size.
+    ParallaxStars,
+    ParallaxMist,
+    ParallaxShapes,
+    ParallaxMotes,
 }
 
 impl AssetKey for ImageKey {
     type Asset = Image;
 }
 
+impl ImageKey {
+    /// How the image is cut into sprites, if it's a sheet without [`Aseprite`] data.
+    pub fn atlas_grid(self) -> Option<AtlasGrid> {
+        match self {
+            ImageKey::Elements | ImageKey::Elements2 => Some(AtlasGrid {
+                cell_size: UVec2::splat(16),
+                columns: 3,
+                rows: 3,
+                padding: Some(UVec2::splat(1)),
+                offset: None,
+            }),
+            ImageKey::Eyes => Some(AtlasGrid {
+                cell_size: UVec2::new(120, 180),
+                columns: 4,
+                rows: 1,
+                padding: None,
+                offset: None,
+            }),
+            ImageKey::Tiles => Some(AtlasGrid {
+                cell_size: UVec2::splat(16),
+                columns: 4,
+                rows: 2,
+                padding: None,
+                offset: None,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// A sprite sheet laid out as a grid, as in [`TextureAtlasLayout::from_grid`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AtlasGrid {
+    pub cell_size: UVec2,
+    pub columns: u32,
+    pub rows: u32,
+    /// Space between cells.
+    pub padding: Option<UVec2>,
+    /// Space before the first cell.
+    pub offset: Option<UVec2>,
+}
+
+/// The layout of every image with an [`ImageKey::atlas_grid`], made once and shared
+/// by everything drawn from it.
+#[derive(Resource, Reflect, Deref)]
+#[reflect(Resource)]
+pub struct AtlasLayouts(HashMap<ImageKey, Handle<TextureAtlasLayout>>);
+
+impl FromWorld for AtlasLayouts {
+    fn from_world(world: &mut World) -> Self {
+        let keys: Vec<_> = world
+            .resource::<HandleMap<ImageKey>>()
+            .keys()
+            .copied()
+            .collect();
+        let mut layouts = world.resource_mut::<Assets<TextureAtlasLayout>>();
+        Self(
+            keys.into_iter()
+                .filter_map(|key| {
+                    let grid = key.atlas_grid()?;
+                    let layout = TextureAtlasLayout::from_grid(
+                        grid.cell_size,
+                        grid.columns,
+                        grid.rows,
+                        grid.padding,
+                        grid.offset,
+                    );
+                    Some((key, layouts.add(layout)))
+                })
+                .collect(),
+        )
+    }
+}
+
 impl FromWorld for HandleMap<ImageKey> {
     fn from_world(world: &mut World) -> Self {
         let asset_server = world.resource::<AssetServer>();
@@ -195,11 +289,135 @@ impl FromWorld for HandleMap<ImageKey> {
                     },
                 ),
             ),
+            (
+                ImageKey::Eyes,
+                asset_server.load_with_settings(
+                    "images/OjosSprite.png",
+                    |settings: &mut ImageLoaderSettings| {
+                        settings.sampler = ImageSampler::nearest();
+                    },
+                ),
+            ),
+            (
+                ImageKey::Tiles,
+                asset_server.load_with_settings(
+                    "images/tiles.png",
+                    |settings: &mut ImageLoaderSettings| {
+                        settings.sampler = ImageSampler::nearest();
+                    },
+                ),
+            ),
+            (
+                ImageKey::ParallaxStars,
+                asset_server.load_with_settings(
+                    "images/parallax/stars.png",
+                    |settings: &mut ImageLoaderSettings| {
+                        settings.sampler = ImageSampler::nearest();
+                    },
+                ),
+            ),
+            (
+                ImageKey::ParallaxMist,
+                asset_server.load_with_settings(
+                    "images/parallax/mist.png",
+                    |settings: &mut ImageLoaderSettings| {
+                        settings.sampler = ImageSampler::nearest();
+                    },
+                ),
+            ),
+            (
+                ImageKey::ParallaxShapes,
+                asset_server.load_with_settings(
+                    "images/parallax/shapes.png",
+                    |settings: &mut ImageLoaderSettings| {
+                        settings.sampler = ImageSampler::nearest();
+                    },
+                ),
+            ),
+            (
+                ImageKey::ParallaxMotes,
+                asset_server.load_with_settings(
+                    "images/parallax/motes.png",
+                    |settings: &mut ImageLoaderSettings| {
+                        settings.sampler = ImageSampler::nearest();
+                    },
+                ),
+            ),
         ]
         .into()
     }
 }
 
+/// Animation data for sprite sheets. The images themselves are still loaded through [`ImageKey`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Reflect)]
+pub enum AsepriteKey {
+    Player,
+    /// Shared by all NPC sheets, which are laid out the same.
+    Npc,
+    PopUp,
+    TitleImage,
+}
+
+impl AssetKey for AsepriteKey {
+    type Asset = Aseprite;
+}
+
+impl FromWorld for HandleMap<AsepriteKey> {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.resource::<AssetServer>();
+        [
+            (
+                AsepriteKey::Player,
+                asset_server.load("images/guy.aseprite.json"),
+            ),
+            (
+                AsepriteKey::Npc,
+                asset_server.load("images/npc.aseprite.json"),
+            ),
+            (
+                AsepriteKey::PopUp,
+                asset_server.load("images/CarotaSheet.aseprite.json"),
+            ),
+            (
+                AsepriteKey::TitleImage,
+                asset_server.load("images/titleimage.aseprite.json"),
+            ),
+        ]
+        .into()
+    }
+}
+
+/// Rooms authored in Tiled, under `rooms/`. Each is named after its file.
+const ROOMS: [&str; 3] = ["intro", "garden", "corridor"];
+
+/// Room layouts by name, so chapter logic can pick them with a string
+/// that matches the file in the editor.
+#[derive(Resource, Reflect, Deref)]
+#[reflect(Resource)]
+pub struct RoomLayouts(HashMap<String, Handle<RoomLayout>>);
+
+impl FromWorld for RoomLayouts {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.resource::<AssetServer>();
+        Self(
+            ROOMS
+                .into_iter()
+                .map(|name| {
+                    let handle = asset_server.load(format!("rooms/{name}.tmj"));
+                    (name.to_string(), handle)
+                })
+                .collect(),
+        )
+    }
+}
+
+impl RoomLayouts {
+    pub fn all_loaded(&self, asset_server: &AssetServer) -> bool {
+        self.values()
+            .all(|x| asset_server.is_loaded_with_dependencies(x))
+    }
+}
+
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Reflect)]
 pub enum SfxKey {
     ButtonHover,
@@ -208,6 +426,7 @@ pub enum SfxKey {
     Step2,
     Step3,
     Step4,
+    Pickup,
 }
 
 impl AssetKey for SfxKey {
@@ -230,6 +449,11 @@ impl FromWorld for HandleMap<SfxKey> {
             (SfxKey::Step2, asset_server.load("audio/sfx/step2.ogg")),
             (SfxKey::Step3, asset_server.load("audio/sfx/step3.ogg")),
             (SfxKey::Step4, asset_server.load("audio/sfx/step4.ogg")),
+            // PLACEHOLDER: the button click stands in until there's a real pickup sound.
+            (
+                SfxKey::Pickup,
+                asset_server.load("audio/sfx/button_press.ogg"),
+            ),
         ]
         .into()
     }
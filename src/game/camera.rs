@@ -0,0 +1,383 @@
+//! Camera that follows the player around rooms larger than the window.
+//! The camera only moves once the target leaves a dead-zone, eases toward it
+//! exponentially, leads a little in the direction of movement,
+//! and stays within the room bounds when there are any.
+//!
+//! [`CameraEffects`] layer shake, zoom pulses and sway on top of that.
+//! Chapter logic triggers them with [`ShakeCamera`], [`PulseZoom`] and [`SwayCamera`].
+
+use std::f32::consts::{PI, TAU};
+
+use bevy::{prelude::*, transform::TransformSystem};
+
+use crate::{
+    game::{movement::MovementController, spawn::level::RoomBounds},
+    screen::Screen,
+    settings::Settings,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<(CameraController, CameraTarget, CameraEffects)>();
+    app.observe(shake_camera)
+        .observe(pulse_zoom)
+        .observe(sway_camera);
+    // Follow after all movement for the frame is done.
+    // Effects are taken off first so they don't feed back into following.
+    app.add_systems(
+        PostUpdate,
+        (
+            remove_camera_effects,
+            sync_camera_bounds.run_if(resource_changed::<RoomBounds>),
+            follow_camera_target,
+            apply_camera_effects,
+        )
+            .chain()
+            .before(TransformSystem::TransformPropagate),
+    );
+    app.add_systems(OnExit(Screen::Playing), reset_camera);
+}
+
+/// Moves the camera toward the [`CameraTarget`].
+#[derive(Component, Reflect, Debug, Clone)]
+#[reflect(Component)]
+pub struct CameraController {
+    /// Size of the box around the camera center the target can move in freely, in pixels.
+    pub dead_zone: Vec2,
+    /// How quickly the camera catches up. Higher is snappier.
+    pub smoothing: f32,
+    /// How far ahead of the target to look in its movement direction, in pixels.
+    pub look_ahead: f32,
+    /// The region the camera view is kept inside of, if any.
+    pub bounds: Option<Rect>,
+    /// Where the target was last frame, used to notice when it wraps.
+    last_target: Option<Vec2>,
+}
+
+impl Default for CameraController {
+    fn default() -> Self {
+        Self {
+            dead_zone: Vec2::new(160.0, 96.0),
+            smoothing: 4.0,
+            look_ahead: 96.0,
+            bounds: None,
+            last_target: None,
+        }
+    }
+}
+
+/// Marks the entity the camera follows.
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[reflect(Component)]
+pub struct CameraTarget;
+
+fn sync_camera_bounds(room: Res<RoomBounds>, mut camera_query: Query<&mut CameraController>) {
+    for mut controller in &mut camera_query {
+        controller.bounds = Some(room.0);
+    }
+}
+
+fn follow_camera_target(
+    time: Res<Time>,
+    target_query: Query<(&Transform, Option<&MovementController>), With<CameraTarget>>,
+    mut camera_query: Query<
+        (
+            &mut CameraController,
+            &mut Transform,
+            &OrthographicProjection,
+        ),
+        Without<CameraTarget>,
+    >,
+) {
+    let Ok((target_transform, movement)) = target_query.get_single() else {
+        return;
+    };
+    let target = target_transform.translation.xy();
+    let direction = movement.map_or(Vec2::ZERO, |movement| movement.0);
+
+    for (mut controller, mut transform, projection) in &mut camera_query {
+        let mut center = transform.translation.xy();
+
+        // The target jumped across the room, so jump with it instead of sliding over.
+        if let Some(last_target) = controller.last_target {
+            let jump = target - last_target;
+            let room_size = controller
+                .bounds
+                .map_or(Vec2::INFINITY, |bounds| bounds.size());
+            if jump.abs().cmpge(room_size / 2.0).any() {
+                center += jump;
+            }
+        }
+        controller.last_target = Some(target);
+
+        // Only follow along an axis once the focus point leaves the dead-zone.
+        let focus = target + direction * controller.look_ahead;
+        let half_dead_zone = controller.dead_zone / 2.0;
+        let offset = focus - center;
+        let goal = center + offset - offset.clamp(-half_dead_zone, half_dead_zone);
+
+        // Frame-rate independent exponential smoothing.
+        let blend = 1.0 - (-controller.smoothing * time.delta_seconds()).exp();
+        center = center.lerp(goal, blend);
+
+        if let Some(bounds) = controller.bounds {
+            center = clamp_view(center, projection.area.half_size(), bounds);
+        }
+        transform.translation = center.extend(transform.translation.z);
+    }
+}
+
+/// Keep a view of the given half size inside `bounds`, centering it when the bounds are smaller.
+fn clamp_view(center: Vec2, half_view: Vec2, bounds: Rect) -> Vec2 {
+    let min = bounds.min + half_view;
+    let max = bounds.max - half_view;
+    Vec2::new(
+        if min.x <= max.x {
+            center.x.clamp(min.x, max.x)
+        } else {
+            bounds.center().x
+        },
+        if min.y <= max.y {
+            center.y.clamp(min.y, max.y)
+        } else {
+            bounds.center().y
+        },
+    )
+}
+
+/// Other screens expect the camera at the origin, and without any effects.
+fn reset_camera(
+    mut camera_query: Query<(
+        &mut CameraController,
+        &mut Transform,
+        Option<&mut CameraEffects>,
+        &mut OrthographicProjection,
+    )>,
+) {
+    for (mut controller, mut transform, effects, mut projection) in &mut camera_query {
+        controller.last_target = None;
+        if let Some(mut effects) = effects {
+            effects.remove_from(&mut transform, &mut projection);
+            *effects = CameraEffects::default();
+        }
+        transform.translation = Vec3::new(0.0, 0.0, transform.translation.z);
+    }
+}
+
+/// Adds trauma to the camera shake. Trauma is capped at 1 and decays over time.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ShakeCamera(pub f32);
+
+/// Zooms in (positive `amount`) or out (negative) and back over `duration` seconds.
+/// An `amount` of 0.2 zooms in by 20% at the peak.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PulseZoom {
+    pub amount: f32,
+    pub duration: f32,
+}
+
+/// Starts a slow sway, or fades it out with `None`.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct SwayCamera(pub Option<Sway>);
+
+/// A slow, looping drift and rotation.
+#[derive(Reflect, Debug, Clone, Copy, PartialEq)]
+pub struct Sway {
+    /// How far the camera drifts along each axis, in pixels.
+    pub drift: Vec2,
+    /// How far the camera rolls to each side, in radians.
+    pub angle: f32,
+    /// Seconds per loop.
+    pub period: f32,
+}
+
+/// Shake, zoom pulses and sway stacked on top of the camera's position.
+#[derive(Component, Reflect, Debug, Clone)]
+#[reflect(Component)]
+pub struct CameraEffects {
+    /// Shake intensity between 0 and 1. The shake itself grows with its square.
+    trauma: f32,
+    pulses: Vec<ZoomPulse>,
+    sway: Option<Sway>,
+    /// Fades the sway in and out so it never pops.
+    sway_weight: f32,
+    sway_on: bool,
+    elapsed: f32,
+    /// What was applied last frame, so it can be taken off again.
+    offset: Vec2,
+    angle: f32,
+    zoom: f32,
+}
+
+impl Default for CameraEffects {
+    fn default() -> Self {
+        Self {
+            trauma: 0.0,
+            pulses: Vec::new(),
+            sway: None,
+            sway_weight: 0.0,
+            sway_on: false,
+            elapsed: 0.0,
+            offset: Vec2::ZERO,
+            angle: 0.0,
+            zoom: 1.0,
+        }
+    }
+}
+
+#[derive(Reflect, Debug, Clone, Copy)]
+struct ZoomPulse {
+    amount: f32,
+    duration: f32,
+    elapsed: f32,
+}
+
+impl CameraEffects {
+    /// Shake offset at full trauma, in pixels.
+    const MAX_SHAKE_OFFSET: f32 = 24.0;
+    /// Shake rotation at full trauma, in radians.
+    const MAX_SHAKE_ANGLE: f32 = 0.05;
+    /// Trauma lost per second.
+    const TRAUMA_DECAY: f32 = 0.8;
+    /// How fast the shake wobbles.
+    const SHAKE_FREQUENCY: f32 = 18.0;
+    /// Seconds for a sway to fade fully in or out.
+    const SWAY_FADE: f32 = 2.0;
+    /// How much of every effect is kept with reduced motion on.
+    const REDUCED_MOTION_SCALE: f32 = 0.25;
+
+    fn remove_from(&mut self, transform: &mut Transform, projection: &mut OrthographicProjection) {
+        transform.translation -= self.offset.extend(0.0);
+        transform.rotate_z(-self.angle);
+        projection.scale /= self.zoom;
+        self.offset = Vec2::ZERO;
+        self.angle = 0.0;
+        self.zoom = 1.0;
+    }
+
+    fn advance(&mut self, delta: f32) {
+        self.elapsed += delta;
+        self.trauma = (self.trauma - Self::TRAUMA_DECAY * delta).max(0.0);
+        for pulse in &mut self.pulses {
+            pulse.elapsed += delta;
+        }
+        self.pulses.retain(|pulse| pulse.elapsed < pulse.duration);
+
+        let fade = delta / Self::SWAY_FADE;
+        self.sway_weight = if self.sway_on {
+            (self.sway_weight + fade).min(1.0)
+        } else {
+            (self.sway_weight - fade).max(0.0)
+        };
+        if self.sway_weight == 0.0 && !self.sway_on {
+            self.sway = None;
+        }
+    }
+
+    /// The offset, rotation and zoom factor of every effect blended together.
+    fn sample(&self) -> (Vec2, f32, f32) {
+        let t = self.elapsed;
+
+        let shake = self.trauma * self.trauma;
+        let mut offset = Self::MAX_SHAKE_OFFSET
+            * shake
+            * Vec2::new(
+                wobble(t * Self::SHAKE_FREQUENCY, 0.0),
+                wobble(t * Self::SHAKE_FREQUENCY, 1.0),
+            );
+        let mut angle = Self::MAX_SHAKE_ANGLE * shake * wobble(t * Self::SHAKE_FREQUENCY, 2.0);
+
+        if let Some(sway) = self.sway {
+            let phase = t * TAU / sway.period;
+            // Different rates per axis trace a slow figure eight.
+            offset += self.sway_weight * sway.drift * Vec2::new(phase.sin(), (phase * 0.5).sin());
+            angle += self.sway_weight * sway.angle * (phase * 0.75).sin();
+        }
+
+        let zoom = self
+            .pulses
+            .iter()
+            .map(|pulse| 1.0 + pulse.amount * (PI * pulse.elapsed / pulse.duration).sin())
+            .product();
+
+        (offset, angle, zoom)
+    }
+}
+
+/// Smooth noise between -1 and 1, different for each `seed`.
+fn wobble(t: f32, seed: f32) -> f32 {
+    let a = (t + seed * 17.0).sin();
+    let b = (t * 2.3 + seed * 5.0).sin();
+    (a + 0.5 * b) / 1.5
+}
+
+fn shake_camera(trigger: Trigger<ShakeCamera>, mut effects_query: Query<&mut CameraEffects>) {
+    for mut effects in &mut effects_query {
+        effects.trauma = (effects.trauma + trigger.event().0).clamp(0.0, 1.0);
+    }
+}
+
+fn pulse_zoom(trigger: Trigger<PulseZoom>, mut effects_query: Query<&mut CameraEffects>) {
+    let PulseZoom { amount, duration } = *trigger.event();
+    if duration <= 0.0 {
+        return;
+    }
+    for mut effects in &mut effects_query {
+        effects.pulses.push(ZoomPulse {
+            amount,
+            duration,
+            elapsed: 0.0,
+        });
+    }
+}
+
+fn sway_camera(trigger: Trigger<SwayCamera>, mut effects_query: Query<&mut CameraEffects>) {
+    for mut effects in &mut effects_query {
+        match trigger.event().0 {
+            Some(sway) => {
+                effects.sway = Some(sway);
+                effects.sway_on = true;
+            }
+            None => effects.sway_on = false,
+        }
+    }
+}
+
+fn remove_camera_effects(
+    mut camera_query: Query<(
+        &mut CameraEffects,
+        &mut Transform,
+        &mut OrthographicProjection,
+    )>,
+) {
+    for (mut effects, mut transform, mut projection) in &mut camera_query {
+        effects.remove_from(&mut transform, &mut projection);
+    }
+}
+
+pub(super) fn apply_camera_effects(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    mut camera_query: Query<(
+        &mut CameraEffects,
+        &mut Transform,
+        &mut OrthographicProjection,
+    )>,
+) {
+    let scale = if settings.reduced_motion {
+        CameraEffects::REDUCED_MOTION_SCALE
+    } else {
+        1.0
+    };
+    for (mut effects, mut transform, mut projection) in &mut camera_query {
+        effects.advance(time.delta_seconds());
+        let (offset, angle, zoom) = effects.sample();
+        effects.offset = offset * scale;
+        effects.angle = angle * scale;
+        // A zoom factor above 1 zooms in, which means a smaller projection scale.
+        effects.zoom = 1.0 / (1.0 + (zoom - 1.0) * scale).max(0.1);
+
+        transform.translation += effects.offset.extend(0.0);
+        transform.rotate_z(effects.angle);
+        projection.scale *= effects.zoom;
+    }
+}
@@ -0,0 +1,211 @@
+//! Named collision layers and the per-chapter rules that decide how they interact.
+//! Each pair of layers can be ignored, only raise sensor events, collide,
+//! or collide and tie the two bodies together with a [`DistanceJoint`].
+
+use avian2d::prelude::*;
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::game::spawn::GameState;
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<(CollisionRole, CollisionRules)>();
+    app.insert_resource(CollisionRules::for_chapter(&GameState::Intro));
+    app.add_systems(
+        Update,
+        (
+            update_collision_rules.run_if(state_changed::<GameState>),
+            apply_collision_rules.run_if(resource_changed::<CollisionRules>),
+            trigger_sensor_events,
+        )
+            .chain(),
+    );
+    app.add_systems(PostProcessCollisions, filter_sensor_contacts);
+}
+
+#[derive(PhysicsLayer, Reflect, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum GameLayer {
+    Player,
+    Spirit,
+    Item,
+    Face,
+    Wall,
+    Sensor,
+}
+
+impl GameLayer {
+    pub const ALL: [GameLayer; 6] = [
+        GameLayer::Player,
+        GameLayer::Spirit,
+        GameLayer::Item,
+        GameLayer::Face,
+        GameLayer::Wall,
+        GameLayer::Sensor,
+    ];
+}
+
+/// The layer an entity was spawned on, kept so its filters can be rebuilt
+/// whenever the [`CollisionRules`] change.
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq, Eq)]
+#[reflect(Component)]
+pub struct CollisionRole(pub GameLayer);
+
+/// How two layers interact.
+#[derive(Reflect, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PairRule {
+    /// The layers pass through each other.
+    #[default]
+    Ignore,
+    /// The layers pass through each other, but touching triggers [`SensorStarted`] and [`SensorEnded`].
+    Sensor,
+    /// The layers collide.
+    Collide,
+    /// The layers collide and get tied together with a [`DistanceJoint`].
+    Joint,
+}
+
+/// The current rules for every pair of layers.
+/// Pairs that are not listed are ignored.
+#[derive(Resource, Reflect, Debug, Clone, Default)]
+#[reflect(Resource)]
+pub struct CollisionRules(HashMap<(GameLayer, GameLayer), PairRule>);
+
+impl CollisionRules {
+    /// The rules used while the given chapter is playing.
+    pub fn for_chapter(chapter: &GameState) -> Self {
+        let rules = Self::default()
+            .with(GameLayer::Player, GameLayer::Wall, PairRule::Collide)
+            .with(GameLayer::Spirit, GameLayer::Wall, PairRule::Collide)
+            .with(GameLayer::Player, GameLayer::Item, PairRule::Sensor)
+            .with(GameLayer::Player, GameLayer::Face, PairRule::Sensor)
+            .with(GameLayer::Player, GameLayer::Sensor, PairRule::Sensor)
+            .with(GameLayer::Spirit, GameLayer::Sensor, PairRule::Sensor)
+            .with(GameLayer::Spirit, GameLayer::Spirit, PairRule::Joint);
+
+        match chapter {
+            GameState::Intro | GameState::First | GameState::Second => {
+                rules.with(GameLayer::Player, GameLayer::Spirit, PairRule::Collide)
+            }
+            // The crowd is too thick to push through, so it just brushes past.
+            GameState::Third => rules.with(GameLayer::Player, GameLayer::Spirit, PairRule::Sensor),
+            // All the little spirits walk with you.
+            GameState::Ending => rules.with(GameLayer::Player, GameLayer::Spirit, PairRule::Joint),
+        }
+    }
+
+    pub fn with(mut self, a: GameLayer, b: GameLayer, rule: PairRule) -> Self {
+        self.set(a, b, rule);
+        self
+    }
+
+    pub fn set(&mut self, a: GameLayer, b: GameLayer, rule: PairRule) {
+        self.0.insert(Self::key(a, b), rule);
+    }
+
+    pub fn get(&self, a: GameLayer, b: GameLayer) -> PairRule {
+        self.0.get(&Self::key(a, b)).copied().unwrap_or_default()
+    }
+
+    /// Build the [`CollisionLayers`] for an entity on the given layer.
+    pub fn layers(&self, layer: GameLayer) -> CollisionLayers {
+        let filters = GameLayer::ALL
+            .into_iter()
+            .filter(|&other| self.get(layer, other) != PairRule::Ignore)
+            .fold(LayerMask::NONE, |mask, other| mask | LayerMask::from(other));
+        CollisionLayers::new(layer, filters)
+    }
+
+    /// The components spawn observers should insert for an entity on the given layer.
+    pub fn bundle(&self, layer: GameLayer) -> (CollisionRole, CollisionLayers) {
+        (CollisionRole(layer), self.layers(layer))
+    }
+
+    fn key(a: GameLayer, b: GameLayer) -> (GameLayer, GameLayer) {
+        (a.min(b), a.max(b))
+    }
+}
+
+fn update_collision_rules(game_state: Res<State<GameState>>, mut rules: ResMut<CollisionRules>) {
+    *rules = CollisionRules::for_chapter(game_state.get());
+}
+
+fn apply_collision_rules(
+    rules: Res<CollisionRules>,
+    mut layers_query: Query<(&CollisionRole, &mut CollisionLayers)>,
+) {
+    for (role, mut layers) in &mut layers_query {
+        *layers = rules.layers(role.0);
+    }
+}
+
+/// Keep sensor pairs in the contact list so avian still reports them,
+/// but drop their manifolds so the solver never pushes them apart.
+fn filter_sensor_contacts(
+    mut collisions: ResMut<Collisions>,
+    rules: Res<CollisionRules>,
+    role_query: Query<&CollisionRole>,
+) {
+    collisions.retain(|contacts| {
+        if let Ok([role1, role2]) = role_query.get_many([contacts.entity1, contacts.entity2]) {
+            if rules.get(role1.0, role2.0) == PairRule::Sensor {
+                contacts.manifolds.clear();
+            }
+        }
+        true
+    });
+}
+
+/// Triggered when two entities on a [`PairRule::Sensor`] pair of layers start touching.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct SensorStarted(pub SensorPair);
+
+/// Triggered when two entities on a [`PairRule::Sensor`] pair of layers stop touching.
+// Nothing listens for this yet.
+#[allow(dead_code)]
+#[derive(Event, Debug, Clone, Copy)]
+pub struct SensorEnded(pub SensorPair);
+
+#[derive(Debug, Clone, Copy)]
+pub struct SensorPair {
+    pub entities: [Entity; 2],
+    pub layers: [GameLayer; 2],
+}
+
+impl SensorPair {
+    /// If one side of the pair is on `layer`, return it first and the other side second.
+    pub fn involving(&self, layer: GameLayer) -> Option<(Entity, Entity)> {
+        if self.layers[0] == layer {
+            Some((self.entities[0], self.entities[1]))
+        } else if self.layers[1] == layer {
+            Some((self.entities[1], self.entities[0]))
+        } else {
+            None
+        }
+    }
+}
+
+fn trigger_sensor_events(
+    mut commands: Commands,
+    mut started: EventReader<CollisionStarted>,
+    mut ended: EventReader<CollisionEnded>,
+    rules: Res<CollisionRules>,
+    role_query: Query<&CollisionRole>,
+) {
+    let sensor_pair = |entity1: Entity, entity2: Entity| {
+        let [role1, role2] = role_query.get_many([entity1, entity2]).ok()?;
+        (rules.get(role1.0, role2.0) == PairRule::Sensor).then_some(SensorPair {
+            entities: [entity1, entity2],
+            layers: [role1.0, role2.0],
+        })
+    };
+
+    for CollisionStarted(entity1, entity2) in started.read() {
+        if let Some(pair) = sensor_pair(*entity1, *entity2) {
+            commands.trigger(SensorStarted(pair));
+        }
+    }
+    for CollisionEnded(entity1, entity2) in ended.read() {
+        if let Some(pair) = sensor_pair(*entity1, *entity2) {
+            commands.trigger(SensorEnded(pair));
+        }
+    }
+}
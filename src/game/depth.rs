@@ -0,0 +1,108 @@
+//! Draw order from position: within a [`SortLayer`], things lower on screen are drawn in front.
+//! Each [`YSort`] entity gets its z set every frame from the y of its anchor,
+//! the point it stands on, so a spirit below the player covers the player and not the other way round.
+
+use bevy::{prelude::*, transform::TransformSystem};
+
+use crate::{game::wrap::update_wrap_ghosts, viewport::PIXEL_SIZE};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<YSort>();
+    // Sort by where things ended up this frame, ghosts included.
+    app.add_systems(
+        PostUpdate,
+        y_sort
+            .after(update_wrap_ghosts)
+            .before(TransformSystem::TransformPropagate),
+    );
+}
+
+/// Groups of sprites that are sorted among themselves. A later layer is always drawn on top.
+#[derive(Reflect, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortLayer {
+    /// Flat things lying on the floor, like items.
+    #[default]
+    Ground,
+    /// The player and NPCs.
+    Actors,
+    /// Things above everyone's heads.
+    Overhead,
+    /// The Carota face, which looms over everything else.
+    Face,
+}
+
+impl SortLayer {
+    /// The z the layer is centered on. Room tiles and backgrounds are below all of these.
+    fn depth(self) -> f32 {
+        match self {
+            SortLayer::Ground => 10.0,
+            SortLayer::Actors => 20.0,
+            SortLayer::Overhead => 30.0,
+            SortLayer::Face => 40.0,
+        }
+    }
+}
+
+/// Sets the z of the entity from its y.
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq, Default)]
+#[reflect(Component)]
+pub struct YSort {
+    pub layer: SortLayer,
+    /// Where the sprite touches the ground, as a y offset from its translation in world units.
+    /// E.g. the feet of a character, which are below its center.
+    pub anchor: f32,
+}
+
+/// The bottom of the 32 pixel character sheets, where their feet are.
+pub const FEET: f32 = -16.0 * PIXEL_SIZE;
+
+impl YSort {
+    pub fn new(layer: SortLayer) -> Self {
+        Self { layer, anchor: 0.0 }
+    }
+
+    pub fn with_anchor(mut self, anchor: f32) -> Self {
+        self.anchor = anchor;
+        self
+    }
+}
+
+/// Each layer spreads its sprites over z from 4 below its depth to 4 above it,
+/// which stays clear of the next layer.
+const MAX_DEPTH_OFFSET: f32 = 4.0;
+/// The z between one sprite and the next in a layer, unless the layer is too crowded to fit.
+const DEPTH_STEP: f32 = 1.0 / 256.0;
+
+/// Ranks the sprites of each layer from the back to the front and spaces them out in z by rank.
+/// Sprites at the same y are told apart by entity, so each gets its own z
+/// and their order doesn't flicker.
+fn y_sort(mut sort_query: Query<(Entity, &YSort, &mut Transform)>) {
+    let mut sorted: Vec<_> = sort_query
+        .iter()
+        .map(|(entity, y_sort, transform)| {
+            let y = transform.translation.y + y_sort.anchor;
+            (y_sort.layer.depth(), y, entity)
+        })
+        .collect();
+    // Higher up the screen is further back.
+    sorted.sort_by(|a, b| {
+        a.0.total_cmp(&b.0)
+            .then(b.1.total_cmp(&a.1))
+            .then(a.2.cmp(&b.2))
+    });
+
+    for layer in sorted.chunk_by(|a, b| a.0 == b.0) {
+        let depth = layer[0].0;
+        let step = DEPTH_STEP.min(2.0 * MAX_DEPTH_OFFSET / layer.len() as f32);
+        for (rank, &(_, _, entity)) in layer.iter().enumerate() {
+            let Ok((_, _, mut transform)) = sort_query.get_mut(entity) else {
+                continue;
+            };
+            let z = depth - MAX_DEPTH_OFFSET + rank as f32 * step;
+            // Avoid marking every transform as changed when nothing moved.
+            if transform.translation.z != z {
+                transform.translation.z = z;
+            }
+        }
+    }
+}
@@ -0,0 +1,223 @@
+//! A dream-like distortion for sprites that gets stronger as the chapters go on.
+//! Sprites opt in with [`DreamSprite`]. Their regular sprite is hidden and a quad with
+//! [`DreamMaterial`] is drawn in its place, following its frame, flip and color.
+//! How strong the effect is comes from the chapter's [`DreamIntensity`].
+
+use bevy::{
+    prelude::*,
+    render::{
+        render_resource::{AsBindGroup, ShaderRef},
+        view::RenderLayers,
+    },
+    sprite::{Material2d, Material2dPlugin, MaterialMesh2dBundle, Mesh2dHandle},
+};
+
+use crate::{game::spawn::GameState, AppSet};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_plugins(Material2dPlugin::<DreamMaterial>::default());
+    app.register_type::<(DreamSprite, DreamIntensity)>();
+    app.init_resource::<DreamIntensity>();
+    app.init_resource::<DreamQuad>();
+    app.add_systems(
+        Update,
+        (
+            update_dream_target.run_if(state_changed::<GameState>),
+            ease_dream_intensity,
+            attach_dream_quads,
+            sync_dream_quads,
+        )
+            .chain()
+            .in_set(AppSet::Update),
+    );
+}
+
+/// Draw this sprite with [`DreamMaterial`].
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[reflect(Component)]
+pub struct DreamSprite;
+
+/// How deep in the dream the current chapter is, between 0 and 1.
+/// The current value eases toward the chapter's target so changes are never abrupt.
+#[derive(Resource, Reflect, Debug, Default)]
+#[reflect(Resource)]
+pub struct DreamIntensity {
+    pub current: f32,
+    pub target: f32,
+}
+
+impl DreamIntensity {
+    /// How fast the current value closes in on the target. Higher is faster.
+    const EASING: f32 = 0.5;
+
+    fn for_chapter(chapter: &GameState) -> f32 {
+        match chapter {
+            GameState::Intro => 0.1,
+            GameState::First => 0.35,
+            GameState::Second => 0.6,
+            GameState::Third => 1.0,
+            // Waking up.
+            GameState::Ending => 0.3,
+        }
+    }
+}
+
+#[derive(Asset, TypePath, AsBindGroup, Debug, Clone)]
+pub struct DreamMaterial {
+    #[uniform(0)]
+    color: Vec4,
+    /// The frame within the texture: min uv in xy, max uv in zw. Swapped to flip.
+    #[uniform(0)]
+    uv_rect: Vec4,
+    #[uniform(0)]
+    intensity: f32,
+    /// Offsets the wobble so sprites don't move in lockstep.
+    #[uniform(0)]
+    seed: f32,
+    #[texture(1)]
+    #[sampler(2)]
+    texture: Handle<Image>,
+}
+
+impl Material2d for DreamMaterial {
+    fn fragment_shader() -> ShaderRef {
+        "shaders/dream.wgsl".into()
+    }
+}
+
+/// The unit quad every dream sprite is drawn on, scaled to the sprite's size.
+#[derive(Resource)]
+struct DreamQuad(Mesh2dHandle);
+
+impl FromWorld for DreamQuad {
+    fn from_world(world: &mut World) -> Self {
+        let mut meshes = world.resource_mut::<Assets<Mesh>>();
+        Self(meshes.add(Rectangle::new(1.0, 1.0)).into())
+    }
+}
+
+/// The quad drawn in place of a [`DreamSprite`].
+#[derive(Component)]
+struct DreamOverlay {
+    quad: Entity,
+    material: Handle<DreamMaterial>,
+}
+
+/// Sprites on this layer aren't drawn by any camera.
+const HIDDEN_LAYER: usize = 31;
+
+fn update_dream_target(game_state: Res<State<GameState>>, mut intensity: ResMut<DreamIntensity>) {
+    intensity.target = DreamIntensity::for_chapter(game_state.get());
+}
+
+fn ease_dream_intensity(time: Res<Time>, mut intensity: ResMut<DreamIntensity>) {
+    if intensity.current == intensity.target {
+        return;
+    }
+    let blend = 1.0 - (-DreamIntensity::EASING * time.delta_seconds()).exp();
+    let current = intensity.current.lerp(intensity.target, blend);
+    // Settle instead of creeping forever.
+    intensity.current = if (current - intensity.target).abs() < 0.001 {
+        intensity.target
+    } else {
+        current
+    };
+}
+
+fn attach_dream_quads(
+    mut commands: Commands,
+    quad: Res<DreamQuad>,
+    mut materials: ResMut<Assets<DreamMaterial>>,
+    sprite_query: Query<(Entity, &Handle<Image>), Added<DreamSprite>>,
+) {
+    for (entity, texture) in &sprite_query {
+        let material = materials.add(DreamMaterial {
+            color: Vec4::ZERO,
+            uv_rect: Vec4::ZERO,
+            intensity: 0.0,
+            seed: (entity.index() as f32 * 0.618).fract() * 100.0,
+            texture: texture.clone_weak(),
+        });
+        let quad = commands
+            .spawn((
+                Name::new("Dream Quad"),
+                MaterialMesh2dBundle {
+                    mesh: quad.0.clone(),
+                    material: material.clone(),
+                    ..default()
+                },
+            ))
+            .set_parent(entity)
+            .id();
+        commands.entity(entity).insert((
+            DreamOverlay { quad, material },
+            RenderLayers::layer(HIDDEN_LAYER),
+        ));
+    }
+}
+
+/// Make each quad show what its sprite would.
+fn sync_dream_quads(
+    intensity: Res<DreamIntensity>,
+    images: Res<Assets<Image>>,
+    layouts: Res<Assets<TextureAtlasLayout>>,
+    mut materials: ResMut<Assets<DreamMaterial>>,
+    sprite_query: Query<(
+        &Sprite,
+        &Handle<Image>,
+        Option<&TextureAtlas>,
+        &DreamOverlay,
+    )>,
+    mut quad_query: Query<&mut Transform>,
+) {
+    for (sprite, texture, atlas, overlay) in &sprite_query {
+        let Some(image) = images.get(texture) else {
+            continue;
+        };
+        let texture_size = image.size().as_vec2();
+        let rect = sprite
+            .rect
+            .or_else(|| {
+                let atlas = atlas?;
+                let layout = layouts.get(&atlas.layout)?;
+                layout.textures.get(atlas.index).map(|rect| rect.as_rect())
+            })
+            .unwrap_or(Rect::from_corners(Vec2::ZERO, texture_size));
+        let size = sprite.custom_size.unwrap_or(rect.size());
+
+        let (mut min, mut max) = (rect.min / texture_size, rect.max / texture_size);
+        if sprite.flip_x {
+            std::mem::swap(&mut min.x, &mut max.x);
+        }
+        if sprite.flip_y {
+            std::mem::swap(&mut min.y, &mut max.y);
+        }
+        if let Ok(mut transform) = quad_query.get_mut(overlay.quad) {
+            let scale = size.extend(1.0);
+            if transform.scale != scale {
+                transform.scale = scale;
+            }
+            // The sprite is drawn offset from its translation by its anchor, so the quad is too.
+            let offset = (-sprite.anchor.as_vec() * size).extend(transform.translation.z);
+            if transform.translation != offset {
+                transform.translation = offset;
+            }
+        }
+
+        let color = LinearRgba::from(sprite.color).to_vec4();
+        let uv_rect = Vec4::new(min.x, min.y, max.x, max.y);
+        // Only touch materials that changed, so they aren't re-uploaded every frame.
+        let Some(material) = materials.get(&overlay.material) else {
+            continue;
+        };
+        if (material.color, material.uv_rect, material.intensity)
+            != (color, uv_rect, intensity.current)
+        {
+            if let Some(material) = materials.get_mut(&overlay.material) {
+                material.color = color;
+                material.uv_rect = uv_rect;
+                material.intensity = intensity.current;
+            }
+        }
+    }
+}
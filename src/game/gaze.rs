@@ -0,0 +1,195 @@
+//! Eyes that follow the player around.
+//! Entities with a [`Gaze`] get a pair of [`Eye`]s from `OjosSprite.png` on top of them.
+//! The eyes shift toward the nearest [`GazeTarget`], dart around it a little, and blink now and then.
+
+use std::ops::Range;
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+    game::assets::{AtlasLayouts, HandleMap, ImageKey},
+    AppSet,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<(Gaze, GazeTarget, Eye)>();
+    app.add_systems(
+        Update,
+        (attach_eyes, tick_gaze_timers, update_eyes)
+            .chain()
+            .in_set(AppSet::Update),
+    );
+}
+
+/// What [`Gaze`]s look at. The nearest one wins.
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[reflect(Component)]
+pub struct GazeTarget;
+
+/// Watches the nearest [`GazeTarget`] with a pair of eyes.
+#[derive(Component, Reflect, Debug, Clone)]
+#[reflect(Component)]
+pub struct Gaze {
+    /// Where the middle of the eyes is, relative to the entity, in its pixels.
+    pub offset: Vec2,
+    /// The size of the eyes relative to the entity. 1 fits them to the Carota face.
+    pub scale: f32,
+    /// How far the eyes move toward the target, in eye pixels.
+    pub reach: f32,
+    /// Time between blinks, picked at random each time.
+    pub blink_interval: Range<f32>,
+    /// Time between darting glances, picked at random each time.
+    pub saccade_interval: Range<f32>,
+    blink: Timer,
+    /// Counts down while the eyes are closed.
+    closed: Timer,
+    saccade: Timer,
+    /// The current dart away from looking straight at the target, in eye pixels.
+    jitter: Vec2,
+}
+
+impl Gaze {
+    /// How long a blink keeps the eyes closed, in seconds.
+    const BLINK_DURATION: f32 = 0.12;
+    /// How quickly the eyes catch up with where they want to look. Higher is snappier.
+    const SMOOTHING: f32 = 12.0;
+
+    pub fn new(offset: Vec2, scale: f32) -> Self {
+        let mut closed = Timer::from_seconds(Self::BLINK_DURATION, TimerMode::Once);
+        // Start with the eyes open.
+        closed.tick(closed.duration());
+        let blink_interval = 2.0..6.0;
+        // Picked like every later one, so eyes made together don't blink in lockstep.
+        let first_blink = rand::thread_rng().gen_range(blink_interval.clone());
+        Self {
+            offset,
+            scale,
+            reach: 6.0,
+            blink_interval,
+            saccade_interval: 0.4..1.6,
+            blink: Timer::from_seconds(first_blink, TimerMode::Once),
+            closed,
+            saccade: Timer::from_seconds(0.5, TimerMode::Once),
+            jitter: Vec2::ZERO,
+        }
+    }
+
+    pub fn with_reach(mut self, reach: f32) -> Self {
+        self.reach = reach;
+        self
+    }
+
+    fn is_closed(&self) -> bool {
+        !self.closed.finished()
+    }
+}
+
+/// One of the eyes of a [`Gaze`].
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub struct Eye {
+    /// Atlas index of the open and closed frames.
+    open: usize,
+    closed: usize,
+    /// Where the eye sits when looking straight ahead, relative to the [`Gaze`] entity.
+    rest: Vec2,
+}
+
+/// The eyes sheet is a left and a right eye, which side by side are as wide as the Carota face.
+const EYE_WIDTH: f32 = 120.0;
+
+/// Atlas indices in `OjosSprite.png`: the left and right eye, open and then closed.
+const LEFT_EYE: (usize, usize) = (0, 2);
+const RIGHT_EYE: (usize, usize) = (1, 3);
+
+fn attach_eyes(
+    mut commands: Commands,
+    image_handles: Res<HandleMap<ImageKey>>,
+    atlas_layouts: Res<AtlasLayouts>,
+    gaze_query: Query<(Entity, &Gaze), Added<Gaze>>,
+) {
+    for (entity, gaze) in &gaze_query {
+        commands.entity(entity).with_children(|children| {
+            for ((open, closed), side) in [(LEFT_EYE, -0.5), (RIGHT_EYE, 0.5)] {
+                let rest = gaze.offset + Vec2::X * side * EYE_WIDTH * gaze.scale;
+                children.spawn((
+                    Name::new("Eye"),
+                    Eye { open, closed, rest },
+                    SpriteBundle {
+                        texture: image_handles[&ImageKey::Eyes].clone_weak(),
+                        // Just above the entity's own sprite.
+                        transform: Transform::from_translation(rest.extend(0.1))
+                            .with_scale(Vec3::splat(gaze.scale)),
+                        ..default()
+                    },
+                    TextureAtlas {
+                        layout: atlas_layouts[&ImageKey::Eyes].clone_weak(),
+                        index: open,
+                    },
+                ));
+            }
+        });
+    }
+}
+
+fn tick_gaze_timers(time: Res<Time>, mut gaze_query: Query<&mut Gaze>) {
+    let mut rng = rand::thread_rng();
+    for mut gaze in &mut gaze_query {
+        let gaze = &mut *gaze;
+        gaze.closed.tick(time.delta());
+        if gaze.blink.tick(time.delta()).just_finished() {
+            gaze.closed.reset();
+            let next = rng.gen_range(gaze.blink_interval.clone());
+            gaze.blink = Timer::from_seconds(next, TimerMode::Once);
+        }
+        if gaze.saccade.tick(time.delta()).just_finished() {
+            // Dart somewhere near the target, mostly staying close.
+            let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+            let distance = rng.gen_range(0.0..0.5) * gaze.reach;
+            gaze.jitter = Vec2::from_angle(angle) * distance;
+            let next = rng.gen_range(gaze.saccade_interval.clone());
+            gaze.saccade = Timer::from_seconds(next, TimerMode::Once);
+        }
+    }
+}
+
+fn update_eyes(
+    time: Res<Time>,
+    target_query: Query<&GlobalTransform, With<GazeTarget>>,
+    gaze_query: Query<(&Gaze, &GlobalTransform, &Children)>,
+    mut eye_query: Query<(&Eye, &mut Transform, &mut TextureAtlas)>,
+) {
+    let blend = 1.0 - (-Gaze::SMOOTHING * time.delta_seconds()).exp();
+    for (gaze, transform, children) in &gaze_query {
+        let position = transform.translation().xy();
+        let target = target_query
+            .iter()
+            .map(|target| target.translation().xy())
+            .min_by(|a, b| {
+                a.distance_squared(position)
+                    .total_cmp(&b.distance_squared(position))
+            });
+        // Look straight ahead with nothing to watch.
+        let look = target.map_or(Vec2::ZERO, |target| {
+            (target - position).normalize_or_zero() * gaze.reach
+        });
+        let look = (look + gaze.jitter).clamp_length_max(gaze.reach) * gaze.scale;
+
+        let mut eyes = eye_query.iter_many_mut(children);
+        while let Some((eye, mut eye_transform, mut atlas)) = eyes.fetch_next() {
+            let wanted = eye.rest + look;
+            let current = eye_transform.translation.xy().lerp(wanted, blend);
+            eye_transform.translation = current.extend(eye_transform.translation.z);
+
+            let index = if gaze.is_closed() {
+                eye.closed
+            } else {
+                eye.open
+            };
+            if atlas.index != index {
+                atlas.index = index;
+            }
+        }
+    }
+}
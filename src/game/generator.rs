@@ -0,0 +1,331 @@
+//! Procedural rooms, as an alternative to the ones authored in Tiled.
+//! A [`RoomTheme`] decides what goes in a room and a seed decides where,
+//! so the same theme and seed always make the same [`RoomLayout`].
+
+use std::ops::RangeInclusive;
+
+use bevy::prelude::*;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::{
+    game::{
+        spawn::{room::ROOM_PIXELS, tiles::ItemKind},
+        tiled::{RoomLayout, RoomSpawn, Tile, TileLayer, Tileset},
+    },
+    viewport::PIXEL_SIZE,
+};
+
+/// Tile size in pixels.
+const TILE_SIZE: f32 = 16.0;
+/// Generated rooms are this many tiles across, the same size as the authored ones.
+const COLUMNS: i32 = (ROOM_PIXELS.x / TILE_SIZE as u32) as i32;
+const ROWS: i32 = (ROOM_PIXELS.y / TILE_SIZE as u32) as i32;
+
+/// Atlas indices in `tiles.png`.
+const FLOOR_TILES: [usize; 3] = [0, 1, 2];
+const WALL_TILE: usize = 3;
+const DECORATION_TILES: [usize; 4] = [4, 5, 6, 7];
+
+/// The kind of room to generate.
+#[derive(Reflect, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RoomTheme {
+    /// Open floor with a scattering of hairs.
+    SparseHairField,
+    /// A walled passage packed with groups of spirits.
+    CrowdedCorridor,
+    /// Almost nothing but the face.
+    EmptyVoidWithFace,
+}
+
+/// What a theme puts in a room.
+struct ThemeParams {
+    /// The chance of each item per free tile.
+    items: &'static [(ItemKind, f32)],
+    /// Items the room always has at least one of, however the rolls go.
+    /// The chapters that use the theme need them to move on.
+    required: &'static [ItemKind],
+    /// How many groups of NPCs there are, and how many NPCs are in each.
+    npc_clusters: u32,
+    npc_cluster_size: RangeInclusive<u32>,
+    /// Winding lanes across the room kept clear of obstacles and items.
+    paths: u32,
+    /// The chance per free tile of starting a clump of walls.
+    obstacles: f32,
+    /// Wall off the top and bottom, leaving gaps to leave through.
+    corridor: bool,
+    face: bool,
+    /// The chance of a decoration per free tile.
+    decoration: f32,
+    /// The chance of a tile having no floor at all.
+    void: f32,
+}
+
+impl RoomTheme {
+    fn params(self) -> ThemeParams {
+        match self {
+            RoomTheme::SparseHairField => ThemeParams {
+                items: &[
+                    (ItemKind::Wisps, 0.006),
+                    (ItemKind::Arc, 0.006),
+                    (ItemKind::Strands, 0.006),
+                    (ItemKind::Snip, 0.004),
+                    (ItemKind::Swoop, 0.006),
+                    (ItemKind::Curl, 0.006),
+                    (ItemKind::Lock, 0.01),
+                    (ItemKind::Tangle, 0.004),
+                    (ItemKind::Crumbs, 0.004),
+                ],
+                required: &[ItemKind::Lock],
+                npc_clusters: 0,
+                npc_cluster_size: 0..=0,
+                paths: 1,
+                obstacles: 0.0,
+                corridor: false,
+                face: false,
+                decoration: 0.15,
+                void: 0.0,
+            },
+            RoomTheme::CrowdedCorridor => ThemeParams {
+                items: &[
+                    (ItemKind::Fern, 0.01),
+                    (ItemKind::Stone, 0.006),
+                    (ItemKind::Ash, 0.004),
+                    (ItemKind::Thread, 0.004),
+                ],
+                required: &[ItemKind::Fern, ItemKind::Lock],
+                npc_clusters: 5,
+                npc_cluster_size: 6..=12,
+                paths: 1,
+                obstacles: 0.03,
+                corridor: true,
+                face: false,
+                decoration: 0.03,
+                void: 0.0,
+            },
+            RoomTheme::EmptyVoidWithFace => ThemeParams {
+                items: &[(ItemKind::Stars, 0.01)],
+                required: &[],
+                npc_clusters: 0,
+                npc_cluster_size: 0..=0,
+                paths: 0,
+                obstacles: 0.0,
+                corridor: false,
+                face: true,
+                decoration: 0.0,
+                void: 0.85,
+            },
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Cell {
+    Free,
+    /// Part of a path, or around something that needs room.
+    Clear,
+    Wall,
+    /// Something has been placed here.
+    Taken,
+}
+
+struct Grid(Vec<Cell>);
+
+impl Grid {
+    fn contains(cell: IVec2) -> bool {
+        (0..COLUMNS).contains(&cell.x) && (0..ROWS).contains(&cell.y)
+    }
+
+    fn get(&self, cell: IVec2) -> Option<Cell> {
+        Self::contains(cell).then(|| self.0[(cell.y * COLUMNS + cell.x) as usize])
+    }
+
+    fn set(&mut self, cell: IVec2, value: Cell) {
+        if Self::contains(cell) {
+            self.0[(cell.y * COLUMNS + cell.x) as usize] = value;
+        }
+    }
+
+    fn cells() -> impl Iterator<Item = IVec2> {
+        (0..ROWS).flat_map(|y| (0..COLUMNS).map(move |x| IVec2::new(x, y)))
+    }
+}
+
+/// The center of a tile in world units, with the room centered on the origin.
+fn to_world(cell: IVec2) -> Vec2 {
+    let pixels = (cell.as_vec2() + 0.5) * TILE_SIZE;
+    let size = Vec2::new(COLUMNS as f32, ROWS as f32) * TILE_SIZE;
+    Vec2::new(pixels.x - size.x / 2.0, size.y / 2.0 - pixels.y) * PIXEL_SIZE
+}
+
+fn tile(cell: IVec2, index: usize) -> Tile {
+    Tile {
+        position: to_world(cell),
+        tileset: 0,
+        index,
+        flip_x: false,
+        flip_y: false,
+    }
+}
+
+/// Make a room of the given theme. `tileset` must be `tiles.png`.
+pub fn generate(theme: RoomTheme, seed: u64, tileset: Tileset) -> RoomLayout {
+    let params = theme.params();
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut grid = Grid(vec![Cell::Free; (COLUMNS * ROWS) as usize]);
+    let mut spawns = Vec::new();
+
+    if params.corridor {
+        for x in 0..COLUMNS {
+            grid.set(IVec2::new(x, 0), Cell::Wall);
+            grid.set(IVec2::new(x, ROWS - 1), Cell::Wall);
+        }
+        // Gaps to walk out of the top and bottom.
+        for y in [0, ROWS - 1] {
+            let gap = rng.gen_range(2..COLUMNS - 5);
+            for x in gap..gap + 3 {
+                grid.set(IVec2::new(x, y), Cell::Clear);
+            }
+        }
+    }
+
+    // Paths wander from the left edge to the right.
+    for _ in 0..params.paths {
+        let mut y = rng.gen_range(2..ROWS - 2);
+        for x in 0..COLUMNS {
+            for dy in 0..2 {
+                grid.set(IVec2::new(x, y + dy), Cell::Clear);
+            }
+            y = (y + rng.gen_range(-1..=1)).clamp(1, ROWS - 3);
+        }
+    }
+
+    let center = IVec2::new(COLUMNS / 2, ROWS / 2);
+    if params.face {
+        for cell in Grid::cells().filter(|cell| cell.distance_squared(center) <= 4) {
+            grid.set(cell, Cell::Clear);
+        }
+        spawns.push(RoomSpawn::Face(to_world(center)));
+    }
+    // Only used if there's no player yet, but keep a spot for them anyway.
+    let start = Grid::cells()
+        .filter(|&cell| grid.get(cell) == Some(Cell::Clear))
+        .min_by_key(|cell| cell.distance_squared(center))
+        .unwrap_or(center);
+    grid.set(start, Cell::Taken);
+    spawns.push(RoomSpawn::Player(to_world(start)));
+
+    // Obstacles grow from a seed tile into small clumps.
+    for cell in Grid::cells() {
+        if grid.get(cell) != Some(Cell::Free) || !rng.gen_bool(params.obstacles as f64) {
+            continue;
+        }
+        let mut cursor = cell;
+        for _ in 0..rng.gen_range(1..=4) {
+            if grid.get(cursor) == Some(Cell::Free) {
+                grid.set(cursor, Cell::Wall);
+            }
+            cursor += *[IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y]
+                .choose(&mut rng)
+                .unwrap();
+        }
+    }
+
+    // Items land in the middle of free tiles, nudged a little so they don't line up.
+    for cell in Grid::cells() {
+        if grid.get(cell) != Some(Cell::Free) {
+            continue;
+        }
+        let roll = rng.gen::<f32>();
+        let mut threshold = 0.0;
+        for &(kind, chance) in params.items {
+            threshold += chance;
+            if roll < threshold {
+                let jitter = Vec2::new(rng.gen_range(-4.0..4.0), rng.gen_range(-4.0..4.0));
+                spawns.push(RoomSpawn::Item(
+                    to_world(cell) + jitter * PIXEL_SIZE,
+                    Some(kind),
+                ));
+                grid.set(cell, Cell::Taken);
+                break;
+            }
+        }
+    }
+
+    for &kind in params.required {
+        if spawns
+            .iter()
+            .any(|spawn| matches!(spawn, RoomSpawn::Item(_, Some(held)) if *held == kind))
+        {
+            continue;
+        }
+        // Off the paths if there's room, but anywhere open will do.
+        let free: Vec<_> = Grid::cells()
+            .filter(|&cell| grid.get(cell) == Some(Cell::Free))
+            .collect();
+        let open: Vec<_> = Grid::cells()
+            .filter(|&cell| grid.get(cell) == Some(Cell::Clear))
+            .collect();
+        let Some(&cell) = free.choose(&mut rng).or_else(|| open.choose(&mut rng)) else {
+            break;
+        };
+        spawns.push(RoomSpawn::Item(to_world(cell), Some(kind)));
+        grid.set(cell, Cell::Taken);
+    }
+
+    for _ in 0..params.npc_clusters {
+        let Some(&origin) = Grid::cells()
+            .filter(|&cell| grid.get(cell) == Some(Cell::Free))
+            .collect::<Vec<_>>()
+            .choose(&mut rng)
+        else {
+            break;
+        };
+        for _ in 0..rng.gen_range(params.npc_cluster_size.clone()) {
+            let spread = Vec2::new(rng.gen_range(-1.5..1.5), rng.gen_range(-1.5..1.5));
+            spawns.push(RoomSpawn::Npc(
+                to_world(origin) + spread * TILE_SIZE * PIXEL_SIZE,
+            ));
+        }
+        grid.set(origin, Cell::Taken);
+    }
+
+    let mut ground = Vec::new();
+    let mut decoration = Vec::new();
+    let mut walls = Vec::new();
+    for cell in Grid::cells() {
+        let kind = grid.get(cell).unwrap();
+        if kind == Cell::Wall {
+            walls.push(tile(cell, WALL_TILE));
+            continue;
+        }
+        if rng.gen_bool(params.void as f64) {
+            continue;
+        }
+        ground.push(tile(cell, *FLOOR_TILES.choose(&mut rng).unwrap()));
+        if kind == Cell::Free && rng.gen_bool(params.decoration as f64) {
+            decoration.push(tile(cell, *DECORATION_TILES.choose(&mut rng).unwrap()));
+        }
+    }
+
+    RoomLayout {
+        tilesets: vec![tileset],
+        layers: vec![
+            TileLayer {
+                name: "Ground".to_string(),
+                solid: false,
+                tiles: ground,
+            },
+            TileLayer {
+                name: "Decoration".to_string(),
+                solid: false,
+                tiles: decoration,
+            },
+            TileLayer {
+                name: "Walls".to_string(),
+                solid: true,
+                tiles: walls,
+            },
+        ],
+        spawns,
+    }
+}
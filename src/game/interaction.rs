@@ -0,0 +1,178 @@
+//! Context-sensitive interaction with things near the player.
+//! The nearest [`Interactable`] in range shows a key prompt,
+//! and pressing [`Action::Interact`] triggers [`Interact`] targeted at it.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{
+    game::{
+        assets::{FontKey, HandleMap},
+        spawn::player::Player,
+    },
+    input::{action_just_pressed, Action},
+    screen::Screen,
+    settings::Settings,
+    tween::{Ease, Repeat, Tween, TweenTarget},
+    ui::prelude::*,
+    viewport::{Letterbox, WorldCamera},
+    AppSet,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<(Interactable, FocusedInteractable)>();
+    app.init_resource::<FocusedInteractable>();
+    app.add_systems(OnEnter(Screen::Playing), spawn_interaction_prompt);
+    app.add_systems(
+        Update,
+        (
+            focus_nearest_interactable,
+            update_interaction_prompt,
+            trigger_interaction.run_if(action_just_pressed(Action::Interact)),
+        )
+            .chain()
+            .in_set(AppSet::Update)
+            .run_if(in_state(Screen::Playing)),
+    );
+}
+
+/// Something the player can interact with when standing within `radius` of it.
+#[derive(Component, Reflect, Debug, Clone)]
+#[reflect(Component)]
+pub struct Interactable {
+    /// How close the player has to be, in pixels.
+    pub radius: f32,
+    /// Shown after the key, e.g. "listen".
+    pub prompt: String,
+}
+
+impl Interactable {
+    pub fn new(radius: f32, prompt: impl Into<String>) -> Self {
+        Self {
+            radius,
+            prompt: prompt.into(),
+        }
+    }
+}
+
+/// Triggered on an [`Interactable`] when the player interacts with it.
+/// Observers can read the target with [`Trigger::entity`].
+#[derive(Event, Debug)]
+pub struct Interact;
+
+/// The interactable the prompt currently points at.
+#[derive(Resource, Reflect, Default, Debug)]
+#[reflect(Resource)]
+pub struct FocusedInteractable(pub Option<Entity>);
+
+fn focus_nearest_interactable(
+    player_query: Query<&GlobalTransform, With<Player>>,
+    interactable_query: Query<(Entity, &GlobalTransform, &Interactable)>,
+    mut focused: ResMut<FocusedInteractable>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        focused.0 = None;
+        return;
+    };
+    let player_position = player_transform.translation().xy();
+
+    let nearest = interactable_query
+        .iter()
+        .map(|(entity, transform, interactable)| {
+            let distance = transform.translation().xy().distance(player_position);
+            (entity, distance, interactable.radius)
+        })
+        .filter(|(_, distance, radius)| distance <= radius)
+        .min_by(|(_, a, _), (_, b, _)| a.total_cmp(b))
+        .map(|(entity, _, _)| entity);
+
+    if focused.0 != nearest {
+        focused.0 = nearest;
+    }
+}
+
+#[derive(Component)]
+struct InteractionPrompt;
+
+fn spawn_interaction_prompt(mut commands: Commands, font_handles: Res<HandleMap<FontKey>>) {
+    commands.spawn((
+        Name::new("Interaction Prompt"),
+        InteractionPrompt,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: font_handles[&FontKey::UiFont].clone_weak(),
+                font_size: 32.0,
+                color: ui_palette::BUTTON_TEXT,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            ..default()
+        }),
+        Visibility::Hidden,
+        // Gently pulse so the prompt catches the eye.
+        Tween::new(
+            Duration::from_millis(700),
+            Ease::SineInOut,
+            TweenTarget::TextAlpha {
+                start: 1.0,
+                end: 0.5,
+            },
+        )
+        .then(
+            Duration::from_millis(700),
+            Ease::SineInOut,
+            TweenTarget::TextAlpha {
+                start: 0.5,
+                end: 1.0,
+            },
+        )
+        .with_repeat(Repeat::Forever),
+        StateScoped(Screen::Playing),
+    ));
+}
+
+fn update_interaction_prompt(
+    focused: Res<FocusedInteractable>,
+    interactable_query: Query<(&GlobalTransform, &Interactable)>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<WorldCamera>>,
+    mut prompt_query: Query<(&mut Text, &mut Style, &mut Visibility), With<InteractionPrompt>>,
+    settings: Res<Settings>,
+) {
+    let Ok((camera, camera_transform)) = camera_query.get_single() else {
+        return;
+    };
+    for (mut text, mut style, mut visibility) in &mut prompt_query {
+        let target = focused
+            .0
+            .and_then(|entity| interactable_query.get(entity).ok())
+            .and_then(|(transform, interactable)| {
+                // Float the prompt a little above the target.
+                let anchor = transform.translation() + Vec3::Y * 48.0;
+                let position = camera.world_to_viewport(camera_transform, anchor)?;
+                let position = Letterbox::canvas_to_ui(position);
+                Some((position, interactable))
+            });
+
+        let Some((position, interactable)) = target else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+        *visibility = Visibility::Visible;
+        style.left = Val::Px(position.x);
+        style.top = Val::Px(position.y);
+        let key = settings.bindings.label(Action::Interact);
+        let prompt = format!("{key} — {}", interactable.prompt);
+        if text.sections[0].value != prompt {
+            text.sections[0].value = prompt;
+        }
+    }
+}
+
+fn trigger_interaction(mut commands: Commands, focused: Res<FocusedInteractable>) {
+    if let Some(entity) = focused.0 {
+        commands.trigger_targets(Interact, entity);
+    }
+}
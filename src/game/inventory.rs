@@ -0,0 +1,179 @@
+//! Picking up items and keeping them across rooms.
+
+use bevy::{prelude::*, ui::Val::*};
+
+use crate::{
+    game::{
+        assets::{AtlasLayouts, FontKey, HandleMap, ImageKey, SfxKey},
+        audio::sfx::PlaySfx,
+        collision::{GameLayer, SensorStarted},
+        interaction::Interact,
+        spawn::{
+            player::Player,
+            tiles::{Item, ItemKind},
+        },
+    },
+    screen::Screen,
+    ui::prelude::*,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<Inventory>();
+    app.init_resource::<Inventory>();
+    app.observe(pick_up_item);
+    app.observe(take_item);
+
+    app.add_systems(
+        OnEnter(Screen::Playing),
+        (reset_inventory, spawn_inventory_strip),
+    );
+    app.add_systems(
+        Update,
+        update_inventory_strip
+            .run_if(in_state(Screen::Playing).and_then(resource_changed::<Inventory>)),
+    );
+}
+
+/// Items the player has collected, in the order they were first found.
+#[derive(Resource, Reflect, Default, Debug)]
+#[reflect(Resource)]
+pub struct Inventory(Vec<(ItemKind, u32)>);
+
+impl Inventory {
+    pub fn add(&mut self, kind: ItemKind) {
+        match self.0.iter_mut().find(|(held, _)| *held == kind) {
+            Some((_, count)) => *count += 1,
+            None => self.0.push((kind, 1)),
+        }
+    }
+
+    pub fn count(&self, kind: ItemKind) -> u32 {
+        self.0
+            .iter()
+            .find(|(held, _)| *held == kind)
+            .map_or(0, |(_, count)| *count)
+    }
+
+    pub fn contains_all(&self, kinds: &[ItemKind]) -> bool {
+        kinds.iter().all(|&kind| self.count(kind) > 0)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (ItemKind, u32)> + '_ {
+        self.0.iter().copied()
+    }
+}
+
+fn reset_inventory(mut inventory: ResMut<Inventory>) {
+    *inventory = Inventory::default();
+}
+
+fn pick_up_item(
+    trigger: Trigger<SensorStarted>,
+    mut commands: Commands,
+    item_query: Query<&Item>,
+    player_query: Query<(), With<Player>>,
+    mut inventory: ResMut<Inventory>,
+) {
+    let Some((item_entity, other)) = trigger.event().0.involving(GameLayer::Item) else {
+        return;
+    };
+    if !player_query.contains(other) {
+        return;
+    }
+    let Ok(item) = item_query.get(item_entity) else {
+        return;
+    };
+    collect_item(&mut commands, &mut inventory, item_entity, item);
+}
+
+/// Items can also be taken from a little further away by interacting with them.
+fn take_item(
+    trigger: Trigger<Interact>,
+    mut commands: Commands,
+    item_query: Query<&Item>,
+    mut inventory: ResMut<Inventory>,
+) {
+    let item_entity = trigger.entity();
+    if let Ok(item) = item_query.get(item_entity) {
+        collect_item(&mut commands, &mut inventory, item_entity, item);
+    }
+}
+
+fn collect_item(commands: &mut Commands, inventory: &mut Inventory, entity: Entity, item: &Item) {
+    inventory.add(item.0);
+    // Each trigger's commands are applied before the next trigger's observers run,
+    // so an item touched and interacted with in the same frame is already gone for the second.
+    commands.entity(entity).despawn_recursive();
+    commands.trigger(PlaySfx::Key(SfxKey::Pickup));
+}
+
+/// The HUD row showing collected items.
+#[derive(Component)]
+struct InventoryStrip;
+
+fn spawn_inventory_strip(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Inventory Strip"),
+        InventoryStrip,
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Px(10.0),
+                left: Px(10.0),
+                column_gap: Px(12.0),
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+        },
+        StateScoped(Screen::Playing),
+    ));
+}
+
+fn update_inventory_strip(
+    mut commands: Commands,
+    inventory: Res<Inventory>,
+    strip_query: Query<Entity, With<InventoryStrip>>,
+    image_handles: Res<HandleMap<ImageKey>>,
+    atlas_layouts: Res<AtlasLayouts>,
+    font_handles: Res<HandleMap<FontKey>>,
+) {
+    for entity in &strip_query {
+        commands
+            .entity(entity)
+            .despawn_descendants()
+            .with_children(|children| {
+                for (kind, count) in inventory.iter() {
+                    children.spawn((
+                        Name::new("Inventory Icon"),
+                        ImageBundle {
+                            style: Style {
+                                width: Px(48.0),
+                                height: Px(48.0),
+                                ..default()
+                            },
+                            image: UiImage::new(image_handles[&kind.image_key()].clone_weak()),
+                            ..default()
+                        },
+                        TextureAtlas {
+                            layout: atlas_layouts[&kind.image_key()].clone_weak(),
+                            index: kind.atlas_index(),
+                        },
+                    ));
+                    if count > 1 {
+                        children.spawn((
+                            Name::new("Inventory Count"),
+                            TextBundle::from_section(
+                                format!("x{count}"),
+                                TextStyle {
+                                    font: font_handles[&FontKey::UiFont].clone_weak(),
+                                    font_size: 24.0,
+                                    color: ui_palette::LABEL_TEXT,
+                                },
+                            ),
+                        ));
+                    }
+                }
+            });
+    }
+}
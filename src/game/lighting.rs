@@ -0,0 +1,250 @@
+//! Darkness over the world, lifted around [`PointLight2d`]s.
+//! A quad in front of everything the world camera sees is drawn with [`LightingMaterial`],
+//! which darkens each pixel by the chapter's [`Lighting`] level, minus the light reaching it.
+//! With more lights than the shader takes, the ones closest to the camera win.
+//! Glows, the lights of everything but the player, go out in chapters that should be pitch dark.
+
+use bevy::{
+    prelude::*,
+    render::render_resource::{AsBindGroup, ShaderRef},
+    sprite::{Material2d, Material2dPlugin, MaterialMesh2dBundle},
+};
+
+use crate::{
+    game::spawn::GameState,
+    screen::Screen,
+    viewport::{WorldCamera, VIEW_SIZE},
+    AppSet,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_plugins(Material2dPlugin::<LightingMaterial>::default());
+    app.register_type::<(Lighting, PointLight2d)>();
+    app.init_resource::<Lighting>();
+    app.add_systems(OnEnter(Screen::Playing), spawn_darkness);
+    app.add_systems(
+        Update,
+        (
+            update_lighting_target.run_if(state_changed::<GameState>),
+            ease_lighting,
+            sync_lights,
+        )
+            .chain()
+            .in_set(AppSet::Update),
+    );
+}
+
+/// How dark the world is away from lights.
+/// The current level eases toward the chapter's target, so nights fall gradually.
+#[derive(Resource, Reflect, Debug)]
+#[reflect(Resource)]
+pub struct Lighting {
+    /// Between 0, fully lit, and 1, black wherever no light reaches.
+    pub ambient: f32,
+    pub target: f32,
+    /// The color of the darkness.
+    pub color: Color,
+    /// Whether lights marked as glows shine.
+    pub glows: bool,
+}
+
+impl Default for Lighting {
+    fn default() -> Self {
+        Self {
+            ambient: 0.0,
+            target: 0.0,
+            color: Color::srgb(0.02, 0.01, 0.05),
+            glows: true,
+        }
+    }
+}
+
+impl Lighting {
+    /// How fast the ambient level closes in on the target. Higher is faster.
+    const EASING: f32 = 0.8;
+
+    /// The target ambient level and whether glows shine in the chapter.
+    fn for_chapter(chapter: &GameState) -> (f32, bool) {
+        match chapter {
+            GameState::Intro => (0.0, true),
+            GameState::First => (0.2, true),
+            GameState::Second => (0.45, true),
+            // A crowd only the player's light reveals.
+            GameState::Third => (0.92, false),
+            GameState::Ending => (0.1, true),
+        }
+    }
+}
+
+/// Lights up the darkness around the entity.
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub struct PointLight2d {
+    /// How far the light reaches, in world units.
+    pub radius: f32,
+    /// How quickly the light fades toward its edge. 1 is linear, higher is softer.
+    pub falloff: f32,
+    /// How much darkness the light lifts at its center, between 0 and 1.
+    pub intensity: f32,
+    pub color: Color,
+    /// Goes out in chapters without glows.
+    pub glow: bool,
+}
+
+impl PointLight2d {
+    pub fn new(radius: f32, intensity: f32) -> Self {
+        Self {
+            radius,
+            falloff: 2.0,
+            intensity,
+            color: Color::WHITE,
+            glow: false,
+        }
+    }
+
+    /// A glow of something in the world rather than a light to see by.
+    pub fn glow(radius: f32, intensity: f32) -> Self {
+        Self {
+            glow: true,
+            ..Self::new(radius, intensity)
+        }
+    }
+
+    pub fn with_falloff(mut self, falloff: f32) -> Self {
+        self.falloff = falloff;
+        self
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+}
+
+/// The most lights the shader takes. Must match `MAX_LIGHTS` in `lighting.wgsl`.
+const MAX_LIGHTS: usize = 64;
+
+#[derive(Asset, TypePath, AsBindGroup, Debug, Clone)]
+pub struct LightingMaterial {
+    /// The color of the darkness in rgb, and the ambient level in a.
+    #[uniform(0)]
+    ambient: Vec4,
+    /// Position in xy, radius in z and falloff in w, in world units.
+    #[uniform(0)]
+    lights: [Vec4; MAX_LIGHTS],
+    /// Color in rgb and intensity in a.
+    #[uniform(0)]
+    colors: [Vec4; MAX_LIGHTS],
+    #[uniform(0)]
+    count: u32,
+}
+
+impl Default for LightingMaterial {
+    fn default() -> Self {
+        Self {
+            ambient: Vec4::ZERO,
+            lights: [Vec4::ZERO; MAX_LIGHTS],
+            colors: [Vec4::ZERO; MAX_LIGHTS],
+            count: 0,
+        }
+    }
+}
+
+impl Material2d for LightingMaterial {
+    fn fragment_shader() -> ShaderRef {
+        "shaders/lighting.wgsl".into()
+    }
+}
+
+/// The quad the darkness is drawn on, which moves with the camera.
+#[derive(Component)]
+struct Darkness(Handle<LightingMaterial>);
+
+/// In front of everything else in the world.
+const DARKNESS_DEPTH: f32 = 100.0;
+
+fn spawn_darkness(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<LightingMaterial>>,
+    camera_query: Query<Entity, With<WorldCamera>>,
+) {
+    let Ok(camera) = camera_query.get_single() else {
+        return;
+    };
+    let material = materials.add(LightingMaterial::default());
+    commands
+        .spawn((
+            Name::new("Darkness"),
+            Darkness(material.clone()),
+            MaterialMesh2dBundle {
+                // Big enough to still cover the view when the camera zooms out or turns.
+                mesh: meshes.add(Rectangle::from_size(VIEW_SIZE * 2.0)).into(),
+                material,
+                transform: Transform::from_xyz(0.0, 0.0, DARKNESS_DEPTH),
+                ..default()
+            },
+            StateScoped(Screen::Playing),
+        ))
+        .set_parent(camera);
+}
+
+fn update_lighting_target(game_state: Res<State<GameState>>, mut lighting: ResMut<Lighting>) {
+    (lighting.target, lighting.glows) = Lighting::for_chapter(game_state.get());
+}
+
+fn ease_lighting(time: Res<Time>, mut lighting: ResMut<Lighting>) {
+    if lighting.ambient == lighting.target {
+        return;
+    }
+    let blend = 1.0 - (-Lighting::EASING * time.delta_seconds()).exp();
+    let ambient = lighting.ambient.lerp(lighting.target, blend);
+    // Settle instead of creeping forever.
+    lighting.ambient = if (ambient - lighting.target).abs() < 0.001 {
+        lighting.target
+    } else {
+        ambient
+    };
+}
+
+fn sync_lights(
+    lighting: Res<Lighting>,
+    mut materials: ResMut<Assets<LightingMaterial>>,
+    darkness_query: Query<&Darkness>,
+    camera_query: Query<&GlobalTransform, With<WorldCamera>>,
+    light_query: Query<(&PointLight2d, &GlobalTransform)>,
+) {
+    let Ok(camera) = camera_query.get_single() else {
+        return;
+    };
+    let camera = camera.translation().xy();
+
+    let mut lights: Vec<_> = light_query
+        .iter()
+        .filter(|(light, _)| lighting.glows || !light.glow)
+        .map(|(light, transform)| (light, transform.translation().xy()))
+        .collect();
+    if lights.len() > MAX_LIGHTS {
+        lights.sort_by(|(_, a), (_, b)| {
+            a.distance_squared(camera)
+                .total_cmp(&b.distance_squared(camera))
+        });
+        lights.truncate(MAX_LIGHTS);
+    }
+
+    for darkness in &darkness_query {
+        let Some(material) = materials.get_mut(&darkness.0) else {
+            continue;
+        };
+        material.ambient = LinearRgba::from(lighting.color)
+            .to_vec3()
+            .extend(lighting.ambient);
+        material.count = lights.len() as u32;
+        for (i, (light, position)) in lights.iter().enumerate() {
+            material.lights[i] = position.extend(light.radius).extend(light.falloff);
+            material.colors[i] = LinearRgba::from(light.color)
+                .to_vec3()
+                .extend(light.intensity);
+        }
+    }
+}
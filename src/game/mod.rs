@@ -3,17 +3,49 @@
 use bevy::prelude::*;
 
 pub mod animation;
+pub mod aseprite;
 pub mod assets;
 pub mod audio;
+pub mod camera;
+pub mod collision;
+pub mod depth;
+pub mod dream;
+pub mod gaze;
+pub mod generator;
+pub mod interaction;
+pub mod inventory;
+pub mod lighting;
 mod movement;
+pub mod parallax;
+pub mod particles;
+mod pointer;
 pub mod spawn;
+pub mod tiled;
+mod wrap;
 
 pub(super) fn plugin(app: &mut App) {
     app.add_plugins((
         animation::plugin,
+        aseprite::plugin,
         audio::plugin,
+        camera::plugin,
         assets::plugin,
         movement::plugin,
+        pointer::plugin,
         spawn::plugin,
+        tiled::plugin,
+        collision::plugin,
+        gaze::plugin,
+        interaction::plugin,
+        inventory::plugin,
+        wrap::plugin,
+        // How things are drawn.
+        (
+            depth::plugin,
+            dream::plugin,
+            lighting::plugin,
+            parallax::plugin,
+            particles::plugin,
+        ),
     ));
 }
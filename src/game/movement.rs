@@ -3,59 +3,102 @@
 //! If you want to move the player in a smoother way,
 //! consider using a [fixed timestep](https://github.com/bevyengine/bevy/blob/latest/examples/movement/physics_in_fixed_timestep.rs).
 
-use bevy::{prelude::*, window::PrimaryWindow};
+use bevy::prelude::*;
 
-use crate::game::spawn::level::Counter;
-use crate::AppSet;
+use crate::game::pointer::VirtualJoystick;
+use crate::{
+    input::{Action, ActionState},
+    AppSet,
+};
 
 pub(super) fn plugin(app: &mut App) {
     // Record directional input as movement controls.
-    app.register_type::<MovementController>();
+    app.register_type::<(MovementController, MoveTarget)>();
     app.add_systems(
         Update,
         record_movement_controller.in_set(AppSet::RecordInput),
     );
 
     // Apply movement based on controls.
-    app.register_type::<(Movement, WrapWithinWindow)>();
-    app.add_systems(
-        Update,
-        (apply_movement, wrap_within_window)
-            .chain()
-            .in_set(AppSet::Update),
-    );
+    app.register_type::<Movement>();
+    app.add_systems(Update, apply_movement.in_set(AppSet::Update));
 }
 
 #[derive(Component, Reflect, Default)]
 #[reflect(Component)]
 pub struct MovementController(pub Vec2);
 
+/// A point to steer toward while there is no direct input, set by clicking or tapping.
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+pub struct MoveTarget(pub Option<Vec2>);
+
+impl MoveTarget {
+    /// Within this distance the target counts as reached.
+    const ARRIVAL_RADIUS: f32 = 4.0;
+    /// Within this distance movement eases off so the mover doesn't overshoot.
+    const SLOWING_RADIUS: f32 = 96.0;
+
+    /// The intent that steers from `position` toward the target, clearing the target on arrival.
+    fn steer(&mut self, position: Vec2) -> Vec2 {
+        let Some(target) = self.0 else {
+            return Vec2::ZERO;
+        };
+        let offset = target - position;
+        let distance = offset.length();
+        if distance <= Self::ARRIVAL_RADIUS {
+            self.0 = None;
+            return Vec2::ZERO;
+        }
+        offset / distance * (distance / Self::SLOWING_RADIUS).min(1.0)
+    }
+}
+
 fn record_movement_controller(
-    input: Res<ButtonInput<KeyCode>>,
-    mut controller_query: Query<&mut MovementController>,
+    actions: Res<ActionState>,
+    joystick: Res<VirtualJoystick>,
+    mut controller_query: Query<(&mut MovementController, Option<&mut MoveTarget>, &Transform)>,
 ) {
     // Collect directional input.
     let mut intent = Vec2::ZERO;
-    if input.pressed(KeyCode::KeyW) || input.pressed(KeyCode::ArrowUp) {
+    if actions.pressed(Action::MoveUp) {
         intent.y += 1.0;
     }
-    if input.pressed(KeyCode::KeyS) || input.pressed(KeyCode::ArrowDown) {
+    if actions.pressed(Action::MoveDown) {
         intent.y -= 1.0;
     }
-    if input.pressed(KeyCode::KeyA) || input.pressed(KeyCode::ArrowLeft) {
+    if actions.pressed(Action::MoveLeft) {
         intent.x -= 1.0;
     }
-    if input.pressed(KeyCode::KeyD) || input.pressed(KeyCode::ArrowRight) {
+    if actions.pressed(Action::MoveRight) {
         intent.x += 1.0;
     }
 
     // Normalize so that diagonal movement has the same speed as
     // horizontal and vertical movement.
-    let intent = intent.normalize_or_zero();
+    let mut intent = intent.normalize_or_zero();
+
+    // Fall back to the analog sticks, keeping their magnitude so a light push walks slowly.
+    if intent == Vec2::ZERO {
+        intent = actions.stick();
+    }
+    if intent == Vec2::ZERO {
+        intent = joystick.0;
+    }
 
     // Apply movement intent to controllers.
-    for mut controller in &mut controller_query {
-        controller.0 = intent;
+    for (mut controller, move_target, transform) in &mut controller_query {
+        controller.0 = match move_target {
+            // Direct input cancels any pending click-to-move target.
+            Some(mut move_target) if intent != Vec2::ZERO => {
+                if move_target.0.is_some() {
+                    move_target.0 = None;
+                }
+                intent
+            }
+            Some(mut move_target) => move_target.steer(transform.translation.xy()),
+            None => intent,
+        };
     }
 }
 
@@ -69,7 +112,7 @@ pub struct Movement {
     pub speed: f32,
 }
 
-fn apply_movement(
+pub(super) fn apply_movement(
     time: Res<Time>,
     mut movement_query: Query<(&MovementController, &Movement, &mut Transform)>,
 ) {
@@ -78,32 +121,3 @@ fn apply_movement(
         transform.translation += velocity.extend(0.0) * time.delta_seconds();
     }
 }
-
-#[derive(Component, Reflect)]
-#[reflect(Component)]
-pub struct WrapWithinWindow;
-
-fn wrap_within_window(
-    window_query: Query<&Window, With<PrimaryWindow>>,
-    mut wrap_query: Query<&mut Transform, With<WrapWithinWindow>>,
-    mut counter: ResMut<Counter>,
-) {
-    let size = window_query.single().size() + 256.0;
-    let half_size = size / 2.0;
-    for mut transform in &mut wrap_query {
-        let position = transform.translation.xy();
-        let wrapped = (position + half_size).rem_euclid(size) - half_size;
-
-        // Check if the character has moved out of the screen bounds
-        if (position.x < -half_size.x && wrapped.x >= -half_size.x)
-            || (position.x > half_size.x && wrapped.x <= half_size.x)
-            || (position.y < -half_size.y && wrapped.y >= -half_size.y)
-            || (position.y > half_size.y && wrapped.y <= half_size.y)
-        {
-            counter.0 += 1.0;
-            info!("Moved from the room : {:?} times", counter.0);
-        }
-
-        transform.translation = wrapped.extend(transform.translation.z);
-    }
-}
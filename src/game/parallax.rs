@@ -0,0 +1,325 @@
+//! Background layers that scroll slower than the world to give it depth.
+//! Each layer repeats seamlessly: it is a grid of copies of one image,
+//! kept around the camera and shifted by its scroll modulo the image size.
+//!
+//! Each chapter has its own set of layers. When the chapter changes,
+//! the old set fades out while the new one fades in.
+
+use std::time::Duration;
+
+use bevy::{prelude::*, transform::TransformSystem};
+
+use crate::{
+    game::{
+        assets::{HandleMap, ImageKey},
+        camera::apply_camera_effects,
+        spawn::{player::Player, GameState},
+    },
+    screen::Screen,
+    settings::Settings,
+    tween::Ease,
+    viewport::{WorldCamera, PIXEL_SIZE, VIEW_SIZE},
+    AppSet,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<(ParallaxLayer, Crossfade)>();
+    app.add_systems(
+        Update,
+        (
+            sync_chapter_layers.run_if(in_state(Screen::Playing)),
+            crossfade_layers,
+        )
+            .chain()
+            .in_set(AppSet::Update),
+    );
+    // Scroll once the camera has settled for the frame.
+    app.add_systems(
+        PostUpdate,
+        scroll_layers
+            .after(apply_camera_effects)
+            .before(TransformSystem::TransformPropagate),
+    );
+}
+
+/// What a layer scrolls relative to.
+#[derive(Reflect, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Follow {
+    Camera,
+    Player,
+}
+
+/// A repeating background layer.
+#[derive(Component, Reflect, Debug, Clone)]
+#[reflect(Component)]
+pub struct ParallaxLayer {
+    /// The chapter whose set this layer belongs to.
+    #[reflect(ignore)]
+    pub chapter: GameState,
+    pub follow: Follow,
+    /// How much the layer moves with what it follows.
+    /// 0 stays fixed on screen, 1 moves along with the world.
+    pub speed: f32,
+    /// Constant scrolling, in world units per second.
+    pub drift: Vec2,
+    pub tint: Color,
+    /// The size of one copy of the image, in world units.
+    size: Vec2,
+    /// How far the layer has scrolled, kept within one image.
+    scroll: Vec2,
+    /// Where the followed thing was last frame.
+    last_source: Option<Vec2>,
+    opacity: f32,
+}
+
+/// Fades a layer's opacity. A layer faded out completely is despawned.
+#[derive(Component, Reflect, Debug, Clone)]
+#[reflect(Component)]
+struct Crossfade {
+    timer: Timer,
+    from: f32,
+    to: f32,
+}
+
+/// How long the old chapter's layers take to give way to the new ones.
+const CROSSFADE_DURATION: Duration = Duration::from_secs(2);
+
+/// Farthest layer first. Room tiles are drawn above all of these.
+const BACKGROUND_DEPTH: f32 = -100.0;
+
+/// Movement slower than this per frame is drift, faster is wrapping or teleporting.
+const MAX_FOLLOW_STEP: f32 = VIEW_SIZE.y / 2.0;
+
+/// Drift is slowed down this much with reduced motion on.
+const REDUCED_MOTION_SCALE: f32 = 0.25;
+
+struct LayerDef {
+    image: ImageKey,
+    follow: Follow,
+    speed: f32,
+    drift: Vec2,
+    tint: Color,
+}
+
+impl LayerDef {
+    fn new(image: ImageKey, follow: Follow, speed: f32) -> Self {
+        Self {
+            image,
+            follow,
+            speed,
+            drift: Vec2::ZERO,
+            tint: Color::WHITE,
+        }
+    }
+
+    fn drift(mut self, drift: Vec2) -> Self {
+        self.drift = drift;
+        self
+    }
+
+    fn tint(mut self, tint: Color) -> Self {
+        self.tint = tint;
+        self
+    }
+}
+
+/// Each chapter's layers, from farthest to nearest.
+fn chapter_layers(chapter: &GameState) -> Vec<LayerDef> {
+    use ImageKey::*;
+    match chapter {
+        GameState::Intro => vec![
+            LayerDef::new(ParallaxStars, Follow::Camera, 0.05),
+            LayerDef::new(ParallaxMist, Follow::Camera, 0.2)
+                .drift(Vec2::new(12.0, 0.0))
+                .tint(Color::WHITE.with_alpha(0.6)),
+        ],
+        GameState::First => vec![
+            LayerDef::new(ParallaxStars, Follow::Camera, 0.05),
+            LayerDef::new(ParallaxShapes, Follow::Camera, 0.3),
+            LayerDef::new(ParallaxMotes, Follow::Player, 0.6).drift(Vec2::new(0.0, 8.0)),
+        ],
+        GameState::Second => vec![
+            LayerDef::new(ParallaxMist, Follow::Camera, 0.1)
+                .drift(Vec2::new(-8.0, 0.0))
+                .tint(Color::srgb(0.8, 0.9, 1.0)),
+            LayerDef::new(ParallaxShapes, Follow::Camera, 0.4),
+            LayerDef::new(ParallaxMotes, Follow::Player, 0.7).drift(Vec2::new(6.0, 6.0)),
+        ],
+        GameState::Third => vec![
+            LayerDef::new(ParallaxStars, Follow::Camera, 0.05).tint(Color::srgb(1.0, 0.6, 0.6)),
+            LayerDef::new(ParallaxMist, Follow::Camera, 0.25)
+                .drift(Vec2::new(24.0, -6.0))
+                .tint(Color::srgba(1.0, 0.7, 0.8, 0.8)),
+            LayerDef::new(ParallaxShapes, Follow::Player, 0.5)
+                .drift(Vec2::new(-16.0, 0.0))
+                .tint(Color::srgb(1.0, 0.7, 0.7)),
+        ],
+        GameState::Ending => vec![
+            LayerDef::new(ParallaxStars, Follow::Camera, 0.02),
+            LayerDef::new(ParallaxMotes, Follow::Camera, 0.3)
+                .drift(Vec2::new(0.0, 20.0))
+                .tint(Color::WHITE.with_alpha(0.7)),
+        ],
+    }
+}
+
+/// Make sure the current chapter's layers are up, fading out any others.
+/// The first set of layers appears at once, since there is nothing to fade from.
+fn sync_chapter_layers(
+    mut commands: Commands,
+    game_state: Res<State<GameState>>,
+    image_handles: Res<HandleMap<ImageKey>>,
+    layer_query: Query<(Entity, &ParallaxLayer, Option<&Crossfade>)>,
+) {
+    let chapter = game_state.get();
+    let mut shown = false;
+    let mut any = false;
+    for (entity, layer, crossfade) in &layer_query {
+        let retiring = crossfade.is_some_and(|crossfade| crossfade.to == 0.0);
+        any |= !retiring;
+        if retiring {
+            continue;
+        }
+        if layer.chapter == *chapter {
+            shown = true;
+        } else {
+            commands.entity(entity).insert(Crossfade {
+                timer: Timer::new(CROSSFADE_DURATION, TimerMode::Once),
+                from: layer.opacity,
+                to: 0.0,
+            });
+        }
+    }
+    if shown {
+        return;
+    }
+
+    let size = VIEW_SIZE;
+    for (i, def) in chapter_layers(chapter).into_iter().enumerate() {
+        let opacity = if any { 0.0 } else { 1.0 };
+        let mut entity = commands.spawn((
+            Name::new(format!("Parallax Layer {i}")),
+            ParallaxLayer {
+                chapter: chapter.clone(),
+                follow: def.follow,
+                speed: def.speed,
+                drift: def.drift,
+                tint: def.tint,
+                size,
+                scroll: Vec2::ZERO,
+                last_source: None,
+                opacity,
+            },
+            SpatialBundle::from_transform(Transform::from_xyz(
+                0.0,
+                0.0,
+                BACKGROUND_DEPTH + i as f32,
+            )),
+            StateScoped(Screen::Playing),
+        ));
+        if any {
+            entity.insert(Crossfade {
+                timer: Timer::new(CROSSFADE_DURATION, TimerMode::Once),
+                from: 0.0,
+                to: 1.0,
+            });
+        }
+        // Enough copies to cover the view however the layer is shifted.
+        entity.with_children(|children| {
+            for y in -1..=1 {
+                for x in -1..=1 {
+                    let offset = Vec2::new(x as f32, y as f32) * size;
+                    children.spawn((
+                        Name::new("Parallax Tile"),
+                        SpriteBundle {
+                            texture: image_handles[&def.image].clone_weak(),
+                            sprite: Sprite {
+                                color: def.tint.with_alpha(def.tint.alpha() * opacity),
+                                ..default()
+                            },
+                            transform: Transform::from_scale(Vec2::splat(PIXEL_SIZE).extend(1.0))
+                                .with_translation(offset.extend(0.0)),
+                            ..default()
+                        },
+                    ));
+                }
+            }
+        });
+    }
+}
+
+fn crossfade_layers(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut layer_query: Query<(Entity, &mut ParallaxLayer, &mut Crossfade, &Children)>,
+    mut sprite_query: Query<&mut Sprite>,
+) {
+    for (entity, mut layer, mut crossfade, children) in &mut layer_query {
+        crossfade.timer.tick(time.delta());
+        let t = Ease::SineInOut.apply(crossfade.timer.fraction());
+        layer.opacity = crossfade.from.lerp(crossfade.to, t);
+
+        let color = layer.tint.with_alpha(layer.tint.alpha() * layer.opacity);
+        let mut sprites = sprite_query.iter_many_mut(children);
+        while let Some(mut sprite) = sprites.fetch_next() {
+            sprite.color = color;
+        }
+
+        if crossfade.timer.finished() {
+            if crossfade.to == 0.0 {
+                commands.entity(entity).despawn_recursive();
+            } else {
+                commands.entity(entity).remove::<Crossfade>();
+            }
+        }
+    }
+}
+
+/// Keep each layer around the camera, shifted by its scroll.
+fn scroll_layers(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    camera_query: Query<&Transform, (With<WorldCamera>, Without<ParallaxLayer>)>,
+    player_query: Query<&Transform, (With<Player>, Without<ParallaxLayer>)>,
+    mut layer_query: Query<(&mut ParallaxLayer, &mut Transform)>,
+) {
+    let Ok(camera) = camera_query.get_single() else {
+        return;
+    };
+    let camera = camera.translation.xy();
+    let player = player_query
+        .get_single()
+        .map(|transform| transform.translation.xy())
+        .ok();
+    let drift_scale = if settings.reduced_motion {
+        REDUCED_MOTION_SCALE
+    } else {
+        1.0
+    };
+
+    for (mut layer, mut transform) in &mut layer_query {
+        let source = match layer.follow {
+            Follow::Camera => Some(camera),
+            Follow::Player => player,
+        };
+        // Only follow smooth movement, so wrapping across the room doesn't jolt the layer.
+        let step = match (layer.last_source, source) {
+            (Some(last), Some(source)) if last.distance(source) < MAX_FOLLOW_STEP => source - last,
+            _ => Vec2::ZERO,
+        };
+        layer.last_source = source;
+
+        // Sliding back less than the world does makes the layer look farther away.
+        let scroll =
+            layer.scroll + layer.drift * drift_scale * time.delta_seconds() - step * layer.speed;
+        layer.scroll = wrap(scroll, layer.size);
+
+        transform.translation.x = camera.x + layer.scroll.x;
+        transform.translation.y = camera.y + layer.scroll.y;
+    }
+}
+
+/// Wrap into `-size / 2..size / 2`.
+fn wrap(value: Vec2, size: Vec2) -> Vec2 {
+    (value + size / 2.0).rem_euclid(size) - size / 2.0
+}
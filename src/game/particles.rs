@@ -0,0 +1,425 @@
+//! Lightweight CPU particles. A [`ParticleEmitter`] keeps its particles in a plain list
+//! and draws them all as quads of one mesh, so thousands of them cost a single entity.
+//! Frames come from the sheets in [`AtlasLayouts`].
+//!
+//! Ambient [`Debris`] uses this for hair, dust and motes floating around the view,
+//! denser or sparser depending on the chapter.
+
+use std::f32::consts::TAU;
+
+use bevy::{
+    prelude::*,
+    render::{
+        mesh::{Indices, PrimitiveTopology},
+        render_asset::RenderAssetUsages,
+        view::NoFrustumCulling,
+    },
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+};
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{
+    game::{
+        assets::{AtlasLayouts, HandleMap, ImageKey},
+        spawn::{tiles::ItemKind, GameState},
+    },
+    screen::Screen,
+    viewport::{WorldCamera, PIXEL_SIZE, VIEW_SIZE},
+    AppSet,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<(ParticleEmitter, Debris)>();
+    app.add_systems(OnEnter(Screen::Playing), spawn_debris);
+    app.add_systems(
+        Update,
+        (
+            (
+                center_debris,
+                update_debris_density.run_if(state_changed::<GameState>),
+            ),
+            update_particles,
+            draw_particles,
+        )
+            .chain()
+            .in_set(AppSet::Update),
+    );
+}
+
+/// Spawns particles around [`ParticleEmitter::center`] and moves them along.
+#[derive(Component, Reflect, Debug, Clone)]
+#[reflect(Component)]
+pub struct ParticleEmitter {
+    /// The sheet to draw particles from, which needs an [`ImageKey::atlas_grid`].
+    pub image: ImageKey,
+    /// Atlas indices to pick from at random for each particle.
+    pub frames: Vec<usize>,
+    /// Particles spawned per second.
+    pub rate: f32,
+    /// The most particles alive at once.
+    pub max_particles: usize,
+    /// The middle of the area particles appear in, in world units.
+    pub center: Vec2,
+    /// The size of the area particles appear in, in world units.
+    pub area: Vec2,
+    /// How long each particle lives, in seconds, picked between the two.
+    pub lifetime: Vec2,
+    /// Starting velocity in world units per second, plus up to `velocity_spread` either way.
+    pub velocity: Vec2,
+    pub velocity_spread: Vec2,
+    /// How hard particles wander off their path, in world units per second squared.
+    pub noise: f32,
+    /// How quickly the wandering changes direction.
+    pub noise_frequency: f32,
+    /// Turning speed in radians per second, picked up to this either way.
+    pub spin: f32,
+    /// Color at birth and at death.
+    pub color: [Color; 2],
+    /// Size at birth and at death, relative to the frame drawn at the usual pixel size.
+    pub scale: [f32; 2],
+    #[reflect(ignore)]
+    particles: Vec<Particle>,
+    /// Particles owed from previous frames, to spawn fractional rates evenly.
+    pending: f32,
+}
+
+impl Default for ParticleEmitter {
+    fn default() -> Self {
+        Self {
+            image: ImageKey::Elements,
+            frames: vec![0],
+            rate: 10.0,
+            max_particles: 1000,
+            center: Vec2::ZERO,
+            area: Vec2::ZERO,
+            lifetime: Vec2::new(1.0, 2.0),
+            velocity: Vec2::ZERO,
+            velocity_spread: Vec2::ZERO,
+            noise: 0.0,
+            noise_frequency: 1.0,
+            spin: 0.0,
+            color: [Color::WHITE, Color::WHITE.with_alpha(0.0)],
+            scale: [1.0, 1.0],
+            particles: Vec::new(),
+            pending: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Particle {
+    position: Vec2,
+    velocity: Vec2,
+    rotation: f32,
+    spin: f32,
+    age: f32,
+    lifetime: f32,
+    frame: usize,
+    /// Makes each particle wander differently.
+    seed: f32,
+}
+
+/// Builds the mesh and material a [`ParticleEmitter`] draws with.
+pub fn particle_bundle(
+    emitter: &ParticleEmitter,
+    depth: f32,
+    image_handles: &HandleMap<ImageKey>,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+) -> impl Bundle {
+    (
+        MaterialMesh2dBundle {
+            mesh: Mesh2dHandle(meshes.add(Mesh::new(
+                PrimitiveTopology::TriangleList,
+                RenderAssetUsages::default(),
+            ))),
+            material: materials.add(ColorMaterial::from(
+                image_handles[&emitter.image].clone_weak(),
+            )),
+            transform: Transform::from_xyz(0.0, 0.0, depth),
+            ..default()
+        },
+        // The mesh changes every frame, so its bounds would always be out of date.
+        NoFrustumCulling,
+    )
+}
+
+fn update_particles(time: Res<Time>, mut emitter_query: Query<&mut ParticleEmitter>) {
+    let dt = time.delta_seconds();
+    let mut rng = rand::thread_rng();
+    for mut emitter in &mut emitter_query {
+        let emitter = &mut *emitter;
+        emitter.particles.retain_mut(|particle| {
+            particle.age += dt;
+            if particle.age >= particle.lifetime {
+                return false;
+            }
+            let t = particle.age * emitter.noise_frequency;
+            let wander = Vec2::new(wobble(t, particle.seed), wobble(t, particle.seed + 0.5));
+            particle.velocity += wander * emitter.noise * dt;
+            particle.position += particle.velocity * dt;
+            particle.rotation += particle.spin * dt;
+            true
+        });
+
+        emitter.pending += emitter.rate * dt;
+        let count = emitter.pending.floor();
+        emitter.pending -= count;
+        let room = emitter
+            .max_particles
+            .saturating_sub(emitter.particles.len());
+        for _ in 0..(count as usize).min(room) {
+            let Some(&frame) = emitter.frames.choose(&mut rng) else {
+                break;
+            };
+            let offset = Vec2::new(rng.gen_range(-0.5..=0.5), rng.gen_range(-0.5..=0.5));
+            let spread = Vec2::new(rng.gen_range(-1.0..=1.0), rng.gen_range(-1.0..=1.0));
+            let particle = Particle {
+                position: emitter.center + offset * emitter.area,
+                velocity: emitter.velocity + spread * emitter.velocity_spread,
+                rotation: rng.gen_range(0.0..TAU),
+                spin: rng.gen_range(-1.0..=1.0) * emitter.spin,
+                age: 0.0,
+                lifetime: rng
+                    .gen_range(emitter.lifetime.x..=emitter.lifetime.y.max(emitter.lifetime.x)),
+                frame,
+                seed: rng.gen(),
+            };
+            emitter.particles.push(particle);
+        }
+    }
+}
+
+/// Rewrite each emitter's mesh with a quad per particle.
+fn draw_particles(
+    mut meshes: ResMut<Assets<Mesh>>,
+    layouts: Res<Assets<TextureAtlasLayout>>,
+    atlas_layouts: Res<AtlasLayouts>,
+    emitter_query: Query<(&ParticleEmitter, &Mesh2dHandle)>,
+) {
+    for (emitter, mesh) in &emitter_query {
+        let Some(layout) = atlas_layouts
+            .get(&emitter.image)
+            .and_then(|handle| layouts.get(handle))
+        else {
+            continue;
+        };
+        let Some(mesh) = meshes.get_mut(&mesh.0) else {
+            continue;
+        };
+
+        let count = emitter.particles.len();
+        let mut positions = Vec::with_capacity(count * 4);
+        let mut uvs = Vec::with_capacity(count * 4);
+        let mut colors = Vec::with_capacity(count * 4);
+        let mut indices = Vec::with_capacity(count * 6);
+        let [start_color, end_color] = emitter.color.map(LinearRgba::from);
+        let sheet_size = layout.size.as_vec2();
+        for particle in &emitter.particles {
+            let Some(rect) = layout.textures.get(particle.frame) else {
+                continue;
+            };
+            let t = particle.age / particle.lifetime;
+            let scale = emitter.scale[0].lerp(emitter.scale[1], t) * PIXEL_SIZE;
+            let half_size = rect.size().as_vec2() * scale / 2.0;
+            let (sin, cos) = particle.rotation.sin_cos();
+            let corner = |x: f32, y: f32| {
+                let offset = Vec2::new(x, y) * half_size;
+                let rotated = Vec2::new(
+                    offset.x * cos - offset.y * sin,
+                    offset.x * sin + offset.y * cos,
+                );
+                (particle.position + rotated).extend(0.0).to_array()
+            };
+            let (min, max) = (
+                rect.min.as_vec2() / sheet_size,
+                rect.max.as_vec2() / sheet_size,
+            );
+            let color = start_color.mix(&end_color, t).to_f32_array();
+
+            let first = positions.len() as u32;
+            positions.extend([
+                corner(-1.0, -1.0),
+                corner(1.0, -1.0),
+                corner(1.0, 1.0),
+                corner(-1.0, 1.0),
+            ]);
+            // Image y goes down, world y goes up.
+            uvs.extend([
+                [min.x, max.y],
+                [max.x, max.y],
+                [max.x, min.y],
+                [min.x, min.y],
+            ]);
+            colors.extend([color; 4]);
+            indices.extend([first, first + 1, first + 2, first, first + 2, first + 3]);
+        }
+        // Keep a degenerate quad around so the mesh is never empty.
+        if positions.is_empty() {
+            positions.extend([[0.0; 3]; 4]);
+            uvs.extend([[0.0; 2]; 4]);
+            colors.extend([[0.0; 4]; 4]);
+            indices.extend([0, 1, 2, 0, 2, 3]);
+        }
+
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+        mesh.insert_indices(Indices::U32(indices));
+    }
+}
+
+/// Smooth noise between -1 and 1, different for each `seed`.
+fn wobble(t: f32, seed: f32) -> f32 {
+    let a = (t + seed * 17.0).sin();
+    let b = (t * 2.3 + seed * 5.0).sin();
+    (a + 0.5 * b) / 1.5
+}
+
+/// Ambient bits floating around the view.
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq, Eq)]
+#[reflect(Component)]
+pub enum Debris {
+    /// Loose hairs, slowly turning.
+    Hair,
+    /// Grit sinking through the air.
+    Dust,
+    /// Soft specks of light rising.
+    Motes,
+}
+
+/// Drawn over everything in the world except the face.
+const DEBRIS_DEPTH: f32 = 35.0;
+
+/// Debris appears a little beyond the view so none pops in at the edges.
+const DEBRIS_AREA: Vec2 = Vec2::new(VIEW_SIZE.x * 1.4, VIEW_SIZE.y * 1.4);
+
+impl Debris {
+    const ALL: [Debris; 3] = [Debris::Hair, Debris::Dust, Debris::Motes];
+
+    fn emitter(self) -> ParticleEmitter {
+        let frames = |kinds: &[ItemKind]| kinds.iter().map(|kind| kind.atlas_index()).collect();
+        match self {
+            Debris::Hair => ParticleEmitter {
+                image: ImageKey::Elements2,
+                frames: frames(&[
+                    ItemKind::Wisps,
+                    ItemKind::Arc,
+                    ItemKind::Strands,
+                    ItemKind::Swoop,
+                    ItemKind::Curl,
+                ]),
+                max_particles: 600,
+                lifetime: Vec2::new(6.0, 10.0),
+                velocity: Vec2::new(20.0, -10.0),
+                velocity_spread: Vec2::new(30.0, 20.0),
+                noise: 40.0,
+                noise_frequency: 0.6,
+                spin: 1.0,
+                color: [Color::WHITE.with_alpha(0.8), Color::WHITE.with_alpha(0.0)],
+                scale: [0.5, 0.5],
+                ..default()
+            },
+            Debris::Dust => ParticleEmitter {
+                image: ImageKey::Elements,
+                frames: frames(&[ItemKind::Ash, ItemKind::Seeds]),
+                max_particles: 2000,
+                lifetime: Vec2::new(4.0, 8.0),
+                velocity: Vec2::new(0.0, -25.0),
+                velocity_spread: Vec2::new(15.0, 10.0),
+                noise: 25.0,
+                noise_frequency: 1.5,
+                spin: 0.5,
+                color: [
+                    Color::srgba(0.8, 0.8, 0.9, 0.7),
+                    Color::srgba(0.6, 0.6, 0.7, 0.0),
+                ],
+                scale: [0.25, 0.15],
+                ..default()
+            },
+            Debris::Motes => ParticleEmitter {
+                image: ImageKey::Elements,
+                frames: frames(&[ItemKind::Stars, ItemKind::Petals]),
+                max_particles: 1500,
+                lifetime: Vec2::new(3.0, 6.0),
+                velocity: Vec2::new(0.0, 30.0),
+                velocity_spread: Vec2::new(20.0, 15.0),
+                noise: 30.0,
+                noise_frequency: 1.0,
+                color: [
+                    Color::srgba(1.0, 0.95, 0.8, 0.9),
+                    Color::srgba(1.0, 0.8, 0.6, 0.0),
+                ],
+                scale: [0.3, 0.05],
+                ..default()
+            },
+        }
+    }
+
+    /// Particles per second in each chapter.
+    fn rate(self, chapter: &GameState) -> f32 {
+        let (hair, dust, motes) = match chapter {
+            GameState::Intro => (1.0, 6.0, 10.0),
+            GameState::First => (4.0, 10.0, 8.0),
+            GameState::Second => (10.0, 20.0, 5.0),
+            GameState::Third => (30.0, 150.0, 2.0),
+            GameState::Ending => (0.0, 4.0, 40.0),
+        };
+        match self {
+            Debris::Hair => hair,
+            Debris::Dust => dust,
+            Debris::Motes => motes,
+        }
+    }
+}
+
+fn spawn_debris(
+    mut commands: Commands,
+    game_state: Res<State<GameState>>,
+    image_handles: Res<HandleMap<ImageKey>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for (i, debris) in Debris::ALL.into_iter().enumerate() {
+        let emitter = ParticleEmitter {
+            rate: debris.rate(game_state.get()),
+            area: DEBRIS_AREA,
+            ..debris.emitter()
+        };
+        commands.spawn((
+            Name::new(format!("{debris:?} Debris")),
+            debris,
+            particle_bundle(
+                &emitter,
+                DEBRIS_DEPTH + i as f32 * 0.1,
+                &image_handles,
+                &mut meshes,
+                &mut materials,
+            ),
+            emitter,
+            StateScoped(Screen::Playing),
+        ));
+    }
+}
+
+fn update_debris_density(
+    game_state: Res<State<GameState>>,
+    mut debris_query: Query<(&Debris, &mut ParticleEmitter)>,
+) {
+    for (debris, mut emitter) in &mut debris_query {
+        emitter.rate = debris.rate(game_state.get());
+    }
+}
+
+/// Keep debris appearing around wherever the camera is.
+fn center_debris(
+    camera_query: Query<&Transform, With<WorldCamera>>,
+    mut debris_query: Query<&mut ParticleEmitter, With<Debris>>,
+) {
+    let Ok(camera) = camera_query.get_single() else {
+        return;
+    };
+    for mut emitter in &mut debris_query {
+        emitter.center = camera.translation.xy();
+    }
+}
@@ -0,0 +1,162 @@
+//! Pointer and touch controls, mainly for visitors playing the web build on a phone.
+//! Clicking or tapping the world sets a [`MoveTarget`] for the player to walk to,
+//! and once touch input is seen an on-screen joystick appears in the corner.
+
+use bevy::{input::InputSystem, prelude::*, ui::Val::*};
+
+use crate::{
+    game::movement::MoveTarget,
+    screen::Screen,
+    settings::Settings,
+    ui::prelude::*,
+    ui::widgets::JoystickKnob,
+    viewport::{Letterbox, WorldCamera},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<VirtualJoystick>();
+    app.init_resource::<VirtualJoystick>();
+    app.add_systems(
+        PreUpdate,
+        (spawn_joystick_on_touch, update_joystick, record_move_target)
+            .chain()
+            .after(InputSystem)
+            .run_if(in_state(Screen::Playing)),
+    );
+    app.add_systems(OnExit(Screen::Playing), reset_joystick);
+}
+
+/// How far the on-screen joystick is pushed, with a length between 0 and 1.
+#[derive(Resource, Reflect, Default, Debug)]
+#[reflect(Resource)]
+pub struct VirtualJoystick(pub Vec2);
+
+/// The joystick's base, along with the touch currently dragging it.
+#[derive(Component, Default)]
+struct JoystickBase {
+    touch: Option<u64>,
+}
+
+const JOYSTICK_SIZE: f32 = 180.0;
+
+fn spawn_joystick_on_touch(
+    mut commands: Commands,
+    touches: Res<Touches>,
+    settings: Res<Settings>,
+    joystick_query: Query<(), With<JoystickBase>>,
+) {
+    if !settings.virtual_joystick || !touches.any_just_pressed() || !joystick_query.is_empty() {
+        return;
+    }
+    commands
+        .spawn((
+            Name::new("Joystick Root"),
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Px(40.0),
+                    bottom: Px(40.0),
+                    ..default()
+                },
+                ..default()
+            },
+            StateScoped(Screen::Playing),
+        ))
+        .with_children(|children| {
+            children
+                .joystick(JOYSTICK_SIZE)
+                .insert(JoystickBase::default());
+        });
+}
+
+fn update_joystick(
+    touches: Res<Touches>,
+    letterbox: Res<Letterbox>,
+    mut joystick: ResMut<VirtualJoystick>,
+    mut base_query: Query<(&mut JoystickBase, &Node, &GlobalTransform)>,
+    mut knob_query: Query<&mut Style, With<JoystickKnob>>,
+) {
+    let Ok((mut base, node, transform)) = base_query.get_single_mut() else {
+        return;
+    };
+    let center = transform.translation().xy();
+    let radius = node.size().x / 2.0;
+
+    // Grab the joystick with a touch that starts on it.
+    if base.touch.is_none() {
+        base.touch = touches
+            .iter_just_pressed()
+            .find(|touch| letterbox.window_to_ui(touch.position()).distance(center) <= radius)
+            .map(|touch| touch.id());
+    }
+
+    let push = match base.touch.and_then(|id| touches.get_pressed(id)) {
+        Some(touch) => {
+            let offset =
+                (letterbox.window_to_ui(touch.position()) - center).clamp_length_max(radius);
+            // UI space points down, the world points up.
+            Vec2::new(offset.x, -offset.y) / radius
+        }
+        None => {
+            base.touch = None;
+            Vec2::ZERO
+        }
+    };
+    if joystick.0 != push {
+        joystick.0 = push;
+    }
+
+    for mut style in &mut knob_query {
+        style.left = Px(push.x * radius);
+        style.top = Px(-push.y * radius);
+    }
+}
+
+fn record_move_target(
+    mouse: Res<ButtonInput<MouseButton>>,
+    touches: Res<Touches>,
+    window_query: Query<&Window>,
+    letterbox: Res<Letterbox>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<WorldCamera>>,
+    base_query: Query<&JoystickBase>,
+    interaction_query: Query<&Interaction>,
+    mut target_query: Query<&mut MoveTarget>,
+) {
+    // Touches on the joystick steer it instead of setting a target.
+    let joystick_touch = base_query.iter().find_map(|base| base.touch);
+    let touch_position = touches
+        .iter_just_pressed()
+        .find(|touch| Some(touch.id()) != joystick_touch)
+        .map(|touch| touch.position());
+    let click_position = mouse
+        .just_pressed(MouseButton::Left)
+        .then(|| window_query.iter().find_map(Window::cursor_position))
+        .flatten();
+    let Some(screen_position) = touch_position.or(click_position) else {
+        return;
+    };
+
+    // Clicks on UI are not meant for the world.
+    if interaction_query
+        .iter()
+        .any(|interaction| *interaction != Interaction::None)
+    {
+        return;
+    }
+
+    let Ok((camera, camera_transform)) = camera_query.get_single() else {
+        return;
+    };
+    let canvas_position = letterbox.window_to_canvas(screen_position);
+    let Some(world_position) = camera.viewport_to_world_2d(camera_transform, canvas_position)
+    else {
+        return;
+    };
+    for mut target in &mut target_query {
+        target.0 = Some(world_position);
+    }
+}
+
+fn reset_joystick(mut joystick: ResMut<VirtualJoystick>) {
+    joystick.0 = Vec2::ZERO;
+}
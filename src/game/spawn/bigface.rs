@@ -1,20 +1,37 @@
-// use avian2d::prelude::*;
+use avian2d::prelude::*;
 use bevy::prelude::*;
 
 // use crate::screen::Screen;
 // use rand::Rng;
 
+use std::time::Duration;
+
 use crate::game::{
-    animation::BasicAnimation,
-    assets::{FontKey, HandleMap, ImageKey},
+    animation::AnimationController,
+    assets::{AsepriteKey, FontKey, HandleMap, ImageKey},
+    collision::{CollisionRules, GameLayer},
+    depth::{SortLayer, YSort},
+    dream::DreamSprite,
+    gaze::Gaze,
+    interaction::{Interact, Interactable},
+    lighting::PointLight2d,
+    spawn::GameState,
 };
 
-use crate::ui::prelude::*;
+use crate::{
+    tween::{Ease, Tween, TweenTarget},
+    ui::prelude::*,
+};
 
 use crate::ui::widgets::VoiceComponent;
 
+use super::spawn_target;
+
 pub(super) fn plugin(app: &mut App) {
     app.observe(spawn_popup)
+        .observe(spawn_popup_at)
+        .observe(restore_popup_at)
+        .observe(listen_to_face)
         //.register_type::<Popup>()
         .insert_resource(TextVoice::default())
         .insert_resource(TextBubbleEntity(Entity::from_raw(0))) // Initialize with a dummy entity
@@ -30,44 +47,46 @@ pub struct TextVoice {
 #[reflect(Component)]
 pub struct FacePopUp;
 
+/// Spawns the face in the middle of the room.
 #[derive(Event, Debug)]
 pub struct SpawnPopUp;
+
+/// Spawns the face at a position in the world.
+/// Trigger it on an entity to build the face there instead of on a new one.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct SpawnPopUpAt(pub Vec2);
+
+/// Spawns only the face at a position in the world, without greeting the player again.
+/// Used to bring back a face the player has already met.
+/// Trigger it on an entity to build the face there instead of on a new one.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct RestorePopUpAt(pub Vec2);
+
 #[derive(Resource)]
 struct TextBubbleEntity(Entity);
 
-fn spawn_popup(
-    _trigger: Trigger<SpawnPopUp>,
+fn spawn_popup(_trigger: Trigger<SpawnPopUp>, mut commands: Commands) {
+    commands.trigger(SpawnPopUpAt(Vec2::ZERO));
+}
+
+fn spawn_popup_at(
+    trigger: Trigger<SpawnPopUpAt>,
     mut commands: Commands,
     image_handles: Res<HandleMap<ImageKey>>,
-    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    aseprite_handles: Res<HandleMap<AsepriteKey>>,
     font_handles: Res<HandleMap<FontKey>>,
     mut text_voice: ResMut<TextVoice>,
     mut text_bubble_entity: ResMut<TextBubbleEntity>,
+    collision_rules: Res<CollisionRules>,
 ) {
-    // Create a texture atlas
-    let layout =
-        TextureAtlasLayout::from_grid(UVec2 { x: 240, y: 180 }, 3, 1, Some(UVec2::splat(1)), None);
-    let texture_atlas_layout = texture_atlas_layouts.add(layout);
-    let translation = Vec3::new(0.0, 0.0, 1.0);
-    let popup_animation = BasicAnimation::new(3);
-
-    //let popup_entity =
-    commands.spawn((
-        Name::new("PopUp"),
-        FacePopUp,
-        SpriteBundle {
-            texture: image_handles[&ImageKey::PopUp].clone_weak(),
-            transform: Transform::from_scale(Vec2::splat(4.0).extend(1.0))
-                .with_translation(translation),
-            ..Default::default()
-        },
-        TextureAtlas {
-            layout: texture_atlas_layout.clone(),
-            index: popup_animation.get_atlas_index(),
-        },
-        popup_animation,
-    ));
-    //.id();
+    spawn_face(
+        &mut commands,
+        trigger.entity(),
+        trigger.event().0,
+        &image_handles,
+        &aseprite_handles,
+        &collision_rules,
+    );
 
     // Spawn the UI root and dialogue bubble
     let bubble_entity = commands
@@ -83,14 +102,109 @@ fn spawn_popup(
     text_bubble_entity.0 = bubble_entity;
 }
 
+fn restore_popup_at(
+    trigger: Trigger<RestorePopUpAt>,
+    mut commands: Commands,
+    image_handles: Res<HandleMap<ImageKey>>,
+    aseprite_handles: Res<HandleMap<AsepriteKey>>,
+    collision_rules: Res<CollisionRules>,
+) {
+    spawn_face(
+        &mut commands,
+        trigger.entity(),
+        trigger.event().0,
+        &image_handles,
+        &aseprite_handles,
+        &collision_rules,
+    );
+}
+
+/// The face itself, without its dialogue bubble.
+fn spawn_face(
+    commands: &mut Commands,
+    target: Entity,
+    position: Vec2,
+    image_handles: &HandleMap<ImageKey>,
+    aseprite_handles: &HandleMap<AsepriteKey>,
+    collision_rules: &CollisionRules,
+) {
+    let translation = position.extend(1.0);
+
+    //let popup_entity =
+    spawn_target(commands, target).insert((
+        Name::new("PopUp"),
+        FacePopUp,
+        SpriteBundle {
+            texture: image_handles[&ImageKey::PopUp].clone_weak(),
+            transform: Transform::from_scale(Vec2::splat(4.0).extend(1.0))
+                .with_translation(translation),
+            ..Default::default()
+        },
+        YSort::new(SortLayer::Face),
+        Gaze::new(Vec2::ZERO, 1.0),
+        PointLight2d::glow(600.0, 0.9)
+            .with_falloff(1.2)
+            .with_color(Color::srgb(1.0, 0.6, 0.3)),
+        AnimationController::new(aseprite_handles[&AsepriteKey::PopUp].clone_weak(), "idle"),
+        RigidBody::Static,
+        Collider::circle(40.0),
+        collision_rules.bundle(GameLayer::Face),
+        Interactable::new(240.0, "listen"),
+        DreamSprite,
+        // Pop into place instead of appearing at full size.
+        Tween::new(
+            Duration::from_millis(600),
+            Ease::BackOut,
+            TweenTarget::Scale {
+                start: Vec3::splat(1.0),
+                end: Vec3::splat(4.0),
+            },
+        )
+        .with(TweenTarget::SpriteColor {
+            start: Color::WHITE.with_alpha(0.0),
+            end: Color::WHITE,
+        }),
+    ));
+    //.id();
+}
+
 fn update_voice_text(
-    mut query: Query<&mut Text, With<VoiceComponent>>,
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Text), With<VoiceComponent>>,
     text_voice: Res<TextVoice>,
 ) {
-    for mut text in query.iter_mut() {
+    for (entity, mut text) in query.iter_mut() {
         //println!("Updating text to: {}", text_voice.text);
-        if !text.sections.is_empty() {
+        if !text.sections.is_empty() && text.sections[0].value != text_voice.text {
             text.sections[0].value = text_voice.text.clone();
+            // Fade each new line in.
+            commands.entity(entity).insert(Tween::new(
+                Duration::from_millis(400),
+                Ease::QuadOut,
+                TweenTarget::TextAlpha {
+                    start: 0.0,
+                    end: 1.0,
+                },
+            ));
         }
     }
 }
+
+fn listen_to_face(
+    trigger: Trigger<Interact>,
+    face_query: Query<(), With<FacePopUp>>,
+    game_state: Res<State<GameState>>,
+    mut text_voice: ResMut<TextVoice>,
+) {
+    if !face_query.contains(trigger.entity()) {
+        return;
+    }
+    text_voice.text = match game_state.get() {
+        GameState::Intro => "I have been looking at you for so long.",
+        GameState::First => "Bring me what you find.",
+        GameState::Second => "Every room is the same room.",
+        GameState::Third => "They all came to see you.",
+        GameState::Ending => "Close your eyes.",
+    }
+    .to_string();
+}
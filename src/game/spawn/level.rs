@@ -5,7 +5,17 @@ use avian2d::{math::*, prelude::*};
 use bevy::prelude::*;
 use std::collections::HashSet;
 
-use crate::screen::Screen;
+use crate::{
+    game::{
+        camera::{PulseZoom, ShakeCamera, Sway, SwayCamera},
+        collision::{CollisionRole, CollisionRules, PairRule},
+        generator::RoomTheme,
+        inventory::Inventory,
+        wrap::{wrap_within_window, ExitRoom},
+    },
+    post_process::{PostProcess, WarpPulse},
+    screen::Screen,
+};
 // use crate::ui::prelude::*;
 
 // use crate::ui::widgets::VoiceComponent;
@@ -14,8 +24,8 @@ use crate::screen::Screen;
 use super::{
     bigface::{FacePopUp, SpawnPopUp, TextVoice},
     npc::{Npc, SpawnNPC},
-    player::SpawnPlayer,
-    tiles::{Item, SpawnItem},
+    room::{GenerateRoom, SpawnRoom, ROOM_SIZE},
+    tiles::{Item, ItemKind, SpawnItem},
     GameState,
 };
 
@@ -37,17 +47,51 @@ pub struct Counter(pub f32);
 #[reflect(Resource)]
 pub struct SpawnControl(pub bool);
 
+/// The area of the current room. Things that wrap do so at its edges,
+/// and the camera stays inside it.
+#[derive(Resource, Reflect, Debug, Clone, Copy, PartialEq)]
+#[reflect(Resource)]
+pub struct RoomBounds(pub Rect);
+
+impl RoomBounds {
+    /// The room size used while the given chapter is playing.
+    fn for_chapter(chapter: &GameState) -> Self {
+        let size = match chapter {
+            // A crowd this big needs room to wander.
+            GameState::Third => Vec2::new(2560.0, 1440.0),
+            // Exactly the room, so what leaves across a wall comes back at the opposite one.
+            _ => ROOM_SIZE,
+        };
+        Self(Rect::from_center_size(Vec2::ZERO, size))
+    }
+}
+
+impl Default for RoomBounds {
+    fn default() -> Self {
+        Self::for_chapter(&GameState::Intro)
+    }
+}
+
 pub(super) fn plugin(app: &mut App) {
     app.observe(spawn_level)
+        .observe(count_room_exit)
         .observe(despawn_everyone)
         .observe(destroy_joints)
         .add_systems(
             Update,
-            (spawn_logic, create_distance_joint_system), //, update_voice_text),
+            (
+                // See this frame's exits before deciding what's next.
+                spawn_logic.after(wrap_within_window),
+                create_distance_joint_system,
+                update_room_bounds.run_if(state_changed::<GameState>),
+                update_post_process.run_if(state_changed::<GameState>),
+            ), //, update_voice_text),
         )
         .insert_state(GameState::Intro)
         .insert_resource(SpawnControl(false))
         .insert_resource(Counter(0.0))
+        .init_resource::<RoomBounds>()
+        .register_type::<(RoomBounds, Tied)>()
         .add_plugins(
             // Add physics plugins and specify a units-per-meter scaling factor, 1 meter = 20 pixels.
             // The unit allows the engine to tune its parameters for the scale of the world, improving stability.
@@ -55,17 +99,42 @@ pub(super) fn plugin(app: &mut App) {
         );
 }
 
+fn count_room_exit(_trigger: Trigger<ExitRoom>, mut counter: ResMut<Counter>) {
+    counter.0 += 1.0;
+    info!("Moved from the room : {:?} times", counter.0);
+}
+
+fn update_room_bounds(game_state: Res<State<GameState>>, mut room: ResMut<RoomBounds>) {
+    *room = RoomBounds::for_chapter(game_state.get());
+}
+
+/// The screen grows darker and grainier the deeper the chapter.
+fn update_post_process(game_state: Res<State<GameState>>, mut post_process: ResMut<PostProcess>) {
+    let (vignette, grain) = match game_state.get() {
+        GameState::Intro => (0.3, 0.03),
+        GameState::First => (0.4, 0.05),
+        GameState::Second => (0.5, 0.08),
+        GameState::Third => (0.65, 0.12),
+        GameState::Ending => (0.2, 0.04),
+    };
+    post_process.vignette.strength = vignette;
+    post_process.grain.strength = grain;
+}
+
+/// The ripple played when a chapter ends.
+const CHAPTER_WARP: WarpPulse = WarpPulse {
+    amount: 0.01,
+    duration: 1.2,
+};
+
 #[derive(Event, Debug)]
 pub struct SpawnLevel;
 
 fn spawn_level(_trigger: Trigger<SpawnLevel>, mut commands: Commands) {
-    commands.trigger(SpawnPlayer);
-    for _ in 0..30 {
-        commands.trigger(SpawnItem);
-    }
+    commands.trigger(SpawnRoom::named("intro"));
 }
 
-fn spawn_logic(
+pub(super) fn spawn_logic(
     mut counter: ResMut<Counter>,
     mut commands: Commands,
     game_state: Res<State<GameState>>,
@@ -73,16 +142,14 @@ fn spawn_logic(
     mut spawn_control: ResMut<SpawnControl>,
     mut text_voice: ResMut<TextVoice>,
     mut next_screen: ResMut<NextState<Screen>>,
+    inventory: Res<Inventory>,
 ) {
     match game_state.get() {
         GameState::Intro => {
             if counter.0 > 1.0 && !spawn_control.0 {
                 commands.trigger(DespawnEveryone);
                 // commands.trigger(DestroyJoints);
-                commands.trigger(SpawnPopUp);
-                for _ in 0..30 {
-                    commands.trigger(SpawnItem);
-                }
+                commands.trigger(SpawnRoom::named("garden"));
                 counter.0 = 0.0;
                 spawn_control.0 = true;
                 text_voice.text = "You should help me find my way".to_string();
@@ -90,11 +157,13 @@ fn spawn_logic(
                 commands.trigger(DespawnEveryone);
                 // commands.trigger(DestroyJoints);
                 text_voice.text = "Hey! Don't Leave".to_string();
+                commands.trigger(ShakeCamera(0.3));
                 for _ in 0..20 {
                     commands.trigger(SpawnItem);
                 }
                 counter.0 = 0.0;
                 spawn_control.0 = false;
+                commands.trigger(CHAPTER_WARP);
                 next_state.set(GameState::First);
             }
         }
@@ -103,23 +172,35 @@ fn spawn_logic(
                 commands.trigger(DespawnEveryone);
                 text_voice.text = "You won't get far...".to_string();
                 // commands.trigger(DestroyJoints);
-                for _ in 1..15 {
-                    commands.trigger(SpawnNPC);
-                }
-                for _ in 0..30 {
-                    commands.trigger(SpawnItem);
-                }
+                commands.trigger(SpawnRoom::named("corridor"));
                 counter.0 = 0.0;
                 spawn_control.0 = true;
+            } else if counter.0 > 2.0
+                && spawn_control.0
+                && !inventory.contains_all(FIRST_REQUIRED_ITEMS)
+            {
+                hold_chapter(
+                    &mut commands,
+                    &mut counter,
+                    &mut text_voice,
+                    FIRST_REQUIRED_ITEMS,
+                );
             } else if counter.0 > 2.0 && spawn_control.0 {
                 //commands.trigger(DespawnEveryone);
                 text_voice.text = "You are trapped here.".to_string();
+                commands.trigger(ShakeCamera(0.6));
+                commands.trigger(PulseZoom {
+                    amount: 0.15,
+                    duration: 1.5,
+                });
                 commands.trigger(DespawnEveryone);
-                for _ in 0..20 {
-                    commands.trigger(SpawnItem);
-                }
+                commands.trigger(GenerateRoom {
+                    theme: RoomTheme::SparseHairField,
+                    seed: room_seed(GameState::Second, 0),
+                });
                 counter.0 = 0.0;
                 spawn_control.0 = false;
+                commands.trigger(CHAPTER_WARP);
                 next_state.set(GameState::Second);
             }
         }
@@ -129,23 +210,33 @@ fn spawn_logic(
                 commands.trigger(DespawnEveryone);
                 text_voice.text = "As do i".to_string();
                 // commands.trigger(DestroyJoints);
-                for _ in 1..100 {
-                    commands.trigger(SpawnNPC);
-                }
-                for _ in 0..30 {
-                    commands.trigger(SpawnItem);
-                }
+                commands.trigger(GenerateRoom {
+                    theme: RoomTheme::CrowdedCorridor,
+                    seed: room_seed(GameState::Second, 1),
+                });
                 counter.0 = 0.0;
                 spawn_control.0 = true;
+            } else if counter.0 > 1.0
+                && spawn_control.0
+                && !inventory.contains_all(SECOND_REQUIRED_ITEMS)
+            {
+                hold_chapter(
+                    &mut commands,
+                    &mut counter,
+                    &mut text_voice,
+                    SECOND_REQUIRED_ITEMS,
+                );
             } else if counter.0 > 1.0 && spawn_control.0 {
                 commands.trigger(DespawnEveryone);
                 text_voice.text = "You can't escape.".to_string();
+                commands.trigger(ShakeCamera(0.8));
                 //commands.trigger(DestroyJoints);
                 for _ in 0..20 {
                     commands.trigger(SpawnItem);
                 }
                 counter.0 = 0.0;
                 spawn_control.0 = false;
+                commands.trigger(CHAPTER_WARP);
                 next_state.set(GameState::Third);
             }
         }
@@ -165,6 +256,10 @@ fn spawn_logic(
                 commands.trigger(DespawnEveryone);
                 // commands.trigger(DestroyJoints);
                 text_voice.text = "All the little spirits walk with you.".to_string();
+                commands.trigger(PulseZoom {
+                    amount: -0.1,
+                    duration: 3.0,
+                });
                 //commands.entity(Player).insert(Npc);
 
                 for _ in 0..20 {
@@ -172,6 +267,7 @@ fn spawn_logic(
                 }
                 counter.0 = 0.0;
                 spawn_control.0 = false;
+                commands.trigger(CHAPTER_WARP);
                 next_state.set(GameState::Ending);
             }
         }
@@ -179,19 +275,28 @@ fn spawn_logic(
             if counter.0 > 0.0 && !spawn_control.0 {
                 commands.trigger(DespawnEveryone);
                 text_voice.text = "Until you wake up".to_string();
+                // The dream starts to come loose.
+                commands.trigger(SwayCamera(Some(Sway {
+                    drift: Vec2::new(12.0, 8.0),
+                    angle: 0.03,
+                    period: 8.0,
+                })));
                 // commands.trigger(DestroyJoints);
                 for _ in 1..15 {
                     commands.trigger(SpawnNPC);
                 }
-                for _ in 0..30 {
-                    commands.trigger(SpawnItem);
-                }
+                commands.trigger(GenerateRoom {
+                    theme: RoomTheme::EmptyVoidWithFace,
+                    seed: room_seed(GameState::Ending, 0),
+                });
                 counter.0 = 0.0;
                 spawn_control.0 = true;
             } else if counter.0 > 20.0 && spawn_control.0 {
                 commands.trigger(DespawnEveryone);
+                commands.trigger(SwayCamera(None));
                 counter.0 = 0.0;
                 spawn_control.0 = false;
+                commands.trigger(CHAPTER_WARP);
                 next_state.set(GameState::Intro);
                 next_screen.set(Screen::Splash);
             }
@@ -199,6 +304,35 @@ fn spawn_logic(
     }
 }
 
+/// A seed for the given room of a chapter, so each room is generated the same every time.
+const fn room_seed(chapter: GameState, room: u32) -> u64 {
+    ((chapter as u64) << 32) | room as u64
+}
+
+/// Items the player must be carrying to leave the First chapter.
+const FIRST_REQUIRED_ITEMS: &[ItemKind] = &[ItemKind::Ring];
+/// Items the player must be carrying to leave the Second chapter.
+const SECOND_REQUIRED_ITEMS: &[ItemKind] = &[ItemKind::Fern, ItemKind::Lock];
+
+/// Keep the player in the current chapter with a fresh room until they carry the required items.
+fn hold_chapter(
+    commands: &mut Commands,
+    counter: &mut Counter,
+    text_voice: &mut TextVoice,
+    required: &[ItemKind],
+) {
+    commands.trigger(DespawnEveryone);
+    for _ in 0..30 {
+        commands.trigger(SpawnItem);
+    }
+    let names: Vec<_> = required
+        .iter()
+        .map(|kind| format!("{kind:?}").to_lowercase())
+        .collect();
+    text_voice.text = format!("Not without the {}.", names.join(" and the "));
+    counter.0 = 0.0;
+}
+
 #[derive(Event, Debug)]
 pub struct DespawnEveryone;
 
@@ -206,42 +340,65 @@ fn despawn_everyone(
     _trigger: Trigger<DespawnEveryone>,
     mut commands: Commands,
     query: Query<Entity, Or<(With<Npc>, With<Item>, With<FacePopUp>)>>,
+    joint_query: Query<(Entity, &DistanceJoint)>,
 ) {
     for entity in query.iter() {
-        commands.entity(entity).despawn();
+        commands.entity(entity).despawn_recursive();
     }
+    // Joints go too, since every body they could tie besides the player is gone.
+    for (entity, joint) in &joint_query {
+        untie(&mut commands, entity, joint);
+    }
+}
+
+/// Marks a body that has been tied to another, so it isn't tied again.
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[reflect(Component)]
+pub struct Tied;
+
+/// The joint that ties two colliding bodies together.
+pub fn tie(entity1: Entity, entity2: Entity) -> DistanceJoint {
+    DistanceJoint::new(entity1, entity2)
+        .with_local_anchor_1(Vector::ZERO)
+        .with_local_anchor_2(Vector::ZERO)
+        .with_rest_length(200.0)
+        .with_linear_velocity_damping(0.0)
+        .with_angular_velocity_damping(0.0)
+        .with_compliance(0.00000001)
 }
 
 // A system that creates a distance joint between colliding entities
+// whose layers are tied together by the current chapter's collision rules.
 fn create_distance_joint_system(
     mut commands: Commands,
-    query: Query<(Entity, &CollidingEntities, &Transform)>, // With<Npc>>,
-    transform_query: Query<(Entity, &Transform)>,           //, With<Npc>>,
-    mut existing_joints: Local<HashSet<Entity>>,            // Track existing joints
+    query: Query<(Entity, &CollidingEntities, &CollisionRole), Without<Tied>>,
+    role_query: Query<&CollisionRole, Without<Tied>>,
+    collision_rules: Res<CollisionRules>,
+    mut tied: Local<HashSet<Entity>>, // Entities tied this frame, before `Tied` is inserted
 ) {
-    for (entity1, colliding_entities, _transform1) in query.iter() {
-        if existing_joints.contains(&entity1) {
+    tied.clear();
+    for (entity1, colliding_entities, role1) in query.iter() {
+        if tied.contains(&entity1) {
             continue;
         }
         for &entity2 in colliding_entities.iter() {
-            if existing_joints.contains(&entity2) {
+            if tied.contains(&entity2) {
                 // Skip if entity2 is already part of a joint
                 continue;
             }
-            if let Ok(_transform2) = transform_query.get(entity2) {
-                commands.spawn(
-                    DistanceJoint::new(entity1, entity2)
-                        .with_local_anchor_1(Vector::ZERO)
-                        .with_local_anchor_2(Vector::ZERO)
-                        .with_rest_length(200.0)
-                        .with_linear_velocity_damping(0.0)
-                        .with_angular_velocity_damping(0.0)
-                        .with_compliance(0.00000001),
-                );
-                // Add both entities to the set of existing joints
-                existing_joints.insert(entity1);
-                existing_joints.insert(entity2);
+            let Ok(role2) = role_query.get(entity2) else {
+                continue;
+            };
+            if collision_rules.get(role1.0, role2.0) != PairRule::Joint {
+                continue;
             }
+            commands.spawn((tie(entity1, entity2), StateScoped(Screen::Playing)));
+            // Mark both entities so they aren't tied again
+            commands.entity(entity1).insert(Tied);
+            commands.entity(entity2).insert(Tied);
+            tied.insert(entity1);
+            tied.insert(entity2);
+            break;
         }
     }
 }
@@ -254,8 +411,19 @@ fn destroy_joints(
     mut commands: Commands,
     query: Query<(Entity, &DistanceJoint)>,
 ) {
-    for (entity, _distance_joint) in query.iter() {
-        commands.entity(entity).despawn();
+    for (entity, distance_joint) in query.iter() {
+        untie(&mut commands, entity, distance_joint);
         //println!("Entity: {:?}, DistanceJoint: {:?}", entity, distance_joint);
     }
 }
+
+/// Despawns a joint and lets both of its ends be tied again.
+fn untie(commands: &mut Commands, joint_entity: Entity, joint: &DistanceJoint) {
+    commands.entity(joint_entity).despawn();
+    // An end may already be gone.
+    for end in [joint.entity1, joint.entity2] {
+        if let Some(mut end) = commands.get_entity(end) {
+            end.remove::<Tied>();
+        }
+    }
+}
@@ -0,0 +1,259 @@
+//! Remember what was in each room, so walking back into one finds it as it was left.
+//! Rooms sit on a grid: when chapter logic clears the room the player walked out of,
+//! they are one room further in the direction they last left it.
+//! Only the most recently visited rooms are kept, up to [`RoomMemory::CAPACITY`].
+
+use std::collections::VecDeque;
+
+use avian2d::prelude::*;
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{
+    game::{gaze::Gaze, wrap::ExitRoom},
+    screen::Screen,
+};
+
+use super::{
+    bigface::{FacePopUp, RestorePopUpAt},
+    level::{spawn_logic, tie, DespawnEveryone, Tied},
+    npc::{Npc, SpawnNpcAt},
+    player::Player,
+    room::{Room, RoomSource, SpawnRoomTiles},
+    tiles::{Item, ItemKind, SpawnItemAt},
+    GameState,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<RoomMemory>();
+    app.observe(note_exit).observe(remember_room);
+    app.add_systems(OnEnter(Screen::Playing), forget_rooms);
+    // Chapter logic may fill the room first, but a remembered room wins.
+    app.add_systems(
+        Update,
+        restore_room
+            .after(spawn_logic)
+            .run_if(in_state(Screen::Playing)),
+    );
+}
+
+/// A room on the grid, per chapter.
+type RoomKey = (GameState, IVec2);
+
+/// The rooms the player has left, and where they are now.
+#[derive(Resource, Debug, Default)]
+pub struct RoomMemory {
+    /// The player's room on the grid.
+    pub current: IVec2,
+    rooms: HashMap<RoomKey, RoomSnapshot>,
+    /// Least recently visited first.
+    visits: VecDeque<RoomKey>,
+    /// A remembered room the player just walked into.
+    pending: Option<RoomKey>,
+    /// Which way the player last left the room, until the room is cleared.
+    exit: Option<IVec2>,
+}
+
+impl RoomMemory {
+    /// How many rooms are remembered before the least recently visited is forgotten.
+    pub const CAPACITY: usize = 12;
+
+    fn visit(&mut self, key: &RoomKey) {
+        self.visits.retain(|visited| visited != key);
+        self.visits.push_back(key.clone());
+    }
+
+    fn store(&mut self, key: RoomKey, snapshot: RoomSnapshot) {
+        self.visit(&key);
+        self.rooms.insert(key, snapshot);
+        while self.visits.len() > Self::CAPACITY {
+            if let Some(forgotten) = self.visits.pop_front() {
+                self.rooms.remove(&forgotten);
+            }
+        }
+    }
+}
+
+/// Everything in a room when the player left it.
+#[derive(Debug)]
+struct RoomSnapshot {
+    source: Option<RoomSource>,
+    things: Vec<(Thing, Vec2)>,
+    joints: Vec<SavedJoint>,
+}
+
+/// Collected items are gone from the room, so they aren't remembered.
+#[derive(Debug)]
+enum Thing {
+    Item(ItemKind),
+    /// The NPC's look, and its eyes if it is a watcher.
+    Npc(Handle<Image>, Option<Box<Gaze>>),
+    Face,
+}
+
+#[derive(Debug)]
+struct SavedJoint {
+    ends: [JointEnd; 2],
+    rest_length: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum JointEnd {
+    /// Index into [`RoomSnapshot::things`].
+    Thing(usize),
+    /// The player leaves with them, so isn't part of the room.
+    Player,
+}
+
+fn forget_rooms(mut memory: ResMut<RoomMemory>) {
+    *memory = RoomMemory::default();
+}
+
+fn note_exit(
+    trigger: Trigger<ExitRoom>,
+    mut memory: ResMut<RoomMemory>,
+    player_query: Query<(), With<Player>>,
+) {
+    if player_query.contains(trigger.entity()) {
+        memory.exit = Some(trigger.event().direction);
+    }
+}
+
+/// Walking out of a room only leaves it once chapter logic clears it,
+/// which may take a few exits. Clearing it for other reasons doesn't move on the grid.
+fn remember_room(
+    _trigger: Trigger<DespawnEveryone>,
+    mut memory: ResMut<RoomMemory>,
+    game_state: Res<State<GameState>>,
+    player_query: Query<(), With<Player>>,
+    room_query: Query<&Room>,
+    item_query: Query<(Entity, &Item, &Transform)>,
+    npc_query: Query<(Entity, &Handle<Image>, Option<&Gaze>, &Transform), With<Npc>>,
+    face_query: Query<(Entity, &Transform), With<FacePopUp>>,
+    joint_query: Query<&DistanceJoint>,
+) {
+    let Some(direction) = memory.exit.take() else {
+        return;
+    };
+
+    let mut entities = Vec::new();
+    let mut things = Vec::new();
+    for (entity, item, transform) in &item_query {
+        entities.push(entity);
+        things.push((Thing::Item(item.0), transform.translation.xy()));
+    }
+    for (entity, texture, gaze, transform) in &npc_query {
+        entities.push(entity);
+        things.push((
+            Thing::Npc(texture.clone_weak(), gaze.cloned().map(Box::new)),
+            transform.translation.xy(),
+        ));
+    }
+    for (entity, transform) in &face_query {
+        entities.push(entity);
+        things.push((Thing::Face, transform.translation.xy()));
+    }
+
+    let end = |entity: Entity| {
+        if player_query.contains(entity) {
+            return Some(JointEnd::Player);
+        }
+        entities
+            .iter()
+            .position(|&saved| saved == entity)
+            .map(JointEnd::Thing)
+    };
+    let joints = joint_query
+        .iter()
+        .filter_map(|joint| {
+            Some(SavedJoint {
+                ends: [end(joint.entity1)?, end(joint.entity2)?],
+                rest_length: joint.rest_length,
+            })
+        })
+        .collect();
+
+    let chapter = game_state.get().clone();
+    let snapshot = RoomSnapshot {
+        source: room_query.iter().next().map(|room| room.0.clone()),
+        things,
+        joints,
+    };
+    let current = memory.current;
+    memory.store((chapter.clone(), current), snapshot);
+
+    memory.current += direction;
+    let next = (chapter, memory.current);
+    if memory.rooms.contains_key(&next) {
+        memory.pending = Some(next);
+    }
+}
+
+fn restore_room(
+    mut commands: Commands,
+    mut memory: ResMut<RoomMemory>,
+    next_state: Res<NextState<GameState>>,
+    player_query: Query<Entity, With<Player>>,
+) {
+    let Some(key) = memory.pending.take() else {
+        return;
+    };
+    // A new chapter starts fresh.
+    if matches!(*next_state, NextState::Pending(_)) {
+        return;
+    }
+    memory.visit(&key);
+    let Some(snapshot) = memory.rooms.get(&key) else {
+        return;
+    };
+
+    commands.trigger(DespawnEveryone);
+    if let Some(source) = &snapshot.source {
+        commands.trigger(SpawnRoomTiles(source.clone()));
+    }
+    // Reserve the entities up front so joints can refer to them.
+    let mut entities = Vec::with_capacity(snapshot.things.len());
+    for (thing, position) in &snapshot.things {
+        let entity = commands.spawn_empty().id();
+        let position = *position;
+        match thing {
+            Thing::Item(kind) => {
+                commands.trigger_targets(
+                    SpawnItemAt {
+                        kind: *kind,
+                        position,
+                    },
+                    entity,
+                );
+            }
+            Thing::Npc(texture, gaze) => {
+                commands.trigger_targets(SpawnNpcAt(position), entity);
+                // Look the same as before rather than like a random NPC,
+                // and only watch the player if it did before.
+                let mut npc = commands.entity(entity);
+                npc.insert(texture.clone_weak());
+                match gaze {
+                    Some(gaze) => npc.insert(Gaze::clone(gaze)),
+                    None => npc.remove::<Gaze>(),
+                };
+            }
+            Thing::Face => commands.trigger_targets(RestorePopUpAt(position), entity),
+        }
+        entities.push(entity);
+    }
+
+    let player = player_query.get_single().ok();
+    for joint in &snapshot.joints {
+        let [Some(entity1), Some(entity2)] = joint.ends.map(|end| match end {
+            JointEnd::Thing(index) => entities.get(index).copied(),
+            JointEnd::Player => player,
+        }) else {
+            continue;
+        };
+        commands.spawn((
+            tie(entity1, entity2).with_rest_length(joint.rest_length),
+            StateScoped(Screen::Playing),
+        ));
+        commands.entity(entity1).insert(Tied);
+        commands.entity(entity2).insert(Tied);
+    }
+}
@@ -2,18 +2,22 @@
 //! [observers](https://docs.rs/bevy/latest/bevy/ecs/prelude/struct.Observer.html)
 //! for this, but you could also use `Events<E>` or `Commands`.
 
-use bevy::prelude::*;
+use bevy::{ecs::system::EntityCommands, prelude::*};
 
 pub mod bigface;
 pub mod level;
+pub mod memory;
 pub mod npc;
 pub mod player;
+pub mod room;
 pub mod tiles;
 
 pub(super) fn plugin(app: &mut App) {
     app.add_plugins((
         level::plugin,
+        memory::plugin,
         player::plugin,
+        room::plugin,
         npc::plugin,
         tiles::plugin,
         bigface::plugin,
@@ -32,3 +36,13 @@ pub enum GameState {
     Third,
     Ending,
 }
+
+/// The entity a spawn observer should build on: the one the event was triggered on,
+/// so callers can reserve entities ahead of time, or else a new one.
+fn spawn_target<'a>(commands: &'a mut Commands, target: Entity) -> EntityCommands<'a> {
+    if target == Entity::PLACEHOLDER {
+        commands.spawn_empty()
+    } else {
+        commands.entity(target)
+    }
+}
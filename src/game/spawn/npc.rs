@@ -2,37 +2,59 @@ use avian2d::prelude::*;
 use bevy::prelude::*;
 
 use crate::screen::Screen;
-use rand::Rng;
+use rand::{seq::SliceRandom, Rng};
 
 use crate::game::{
-    animation::BasicAnimation,
-    assets::{HandleMap, ImageKey},
+    animation::AnimationController,
+    assets::{AsepriteKey, HandleMap, ImageKey},
+    collision::{CollisionRules, GameLayer},
+    depth::{SortLayer, YSort, FEET},
+    dream::DreamSprite,
+    gaze::Gaze,
+    interaction::{Interact, Interactable},
+    lighting::PointLight2d,
+    spawn::bigface::TextVoice,
+    wrap::WrapWithinWindow,
 };
 
+use super::spawn_target;
+
 pub(super) fn plugin(app: &mut App) {
-    app.observe(spawn_npc).register_type::<Npc>();
+    app.observe(spawn_npc)
+        .observe(spawn_npc_at)
+        .observe(listen_to_npc)
+        .register_type::<Npc>();
 }
 
+/// Spawns an NPC somewhere at random.
 #[derive(Event, Debug)]
 pub struct SpawnNPC;
+
+/// Spawns an NPC at a position in the world.
+/// Trigger it on an entity to build the NPC there instead of on a new one.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct SpawnNpcAt(pub Vec2);
+
 #[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
 #[reflect(Component)]
 pub struct Npc;
 
-fn spawn_npc(
-    _trigger: Trigger<SpawnNPC>,
+fn spawn_npc(_trigger: Trigger<SpawnNPC>, mut commands: Commands) {
+    let mut rng = rand::thread_rng();
+    let x = rng.gen_range(-800.0..800.0); // Adjust the range as needed
+    let y = rng.gen_range(-800.0..800.0); // Adjust the range as needed
+    commands.trigger(SpawnNpcAt(Vec2::new(x, y)));
+}
+
+fn spawn_npc_at(
+    trigger: Trigger<SpawnNpcAt>,
     mut commands: Commands,
     image_handles: Res<HandleMap<ImageKey>>,
-    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    aseprite_handles: Res<HandleMap<AsepriteKey>>,
+    collision_rules: Res<CollisionRules>,
 ) {
-    let layout = TextureAtlasLayout::from_grid(UVec2::splat(32), 2, 1, Some(UVec2::splat(1)), None);
-    let texture_atlas_layout = texture_atlas_layouts.add(layout);
-    let npc_animation = BasicAnimation::new(2);
-
     let mut rng = rand::thread_rng();
-    let x = rng.gen_range(-800.0..800.0); // Adjust the range as needed
-    let y = rng.gen_range(-800.0..800.0); // Adjust the range as needed
-    let translation = Vec3::new(x, y, 1.0);
+    let translation = trigger.event().0.extend(1.0);
 
     // Generate a random number between 1 and 3
     let npc_index = rng.gen_range(1..=10);
@@ -53,7 +75,8 @@ fn spawn_npc(
         _ => image_handles[&ImageKey::Npc1].clone_weak(), // Fallback
     };
 
-    commands.spawn((
+    let mut npc = spawn_target(&mut commands, trigger.entity());
+    npc.insert((
         Name::new("NPC"),
         Npc,
         SpriteBundle {
@@ -62,21 +85,55 @@ fn spawn_npc(
                 .with_translation(translation),
             ..Default::default()
         },
-        TextureAtlas {
-            layout: texture_atlas_layout.clone(),
-            index: npc_animation.get_atlas_index(),
-        },
-        npc_animation,
+        YSort::new(SortLayer::Actors).with_anchor(FEET),
+        // A faint glow, so a crowd is still there at the edge of sight.
+        PointLight2d::glow(90.0, 0.3).with_color(Color::srgb(0.7, 0.8, 1.0)),
+        AnimationController::new(aseprite_handles[&AsepriteKey::Npc].clone_weak(), "idle"),
+        WrapWithinWindow::default(),
+        DreamSprite,
         StateScoped(Screen::Playing),
-        RigidBody::Dynamic,
-        Collider::rectangle(10.0, 10.0),
-        GravityScale(0.0),
-        Friction::new(1.0),
-        LinearDamping(5.0),
-        {
-            let locked_axes = LockedAxes::ROTATION_LOCKED;
-            locked_axes.lock_translation_y();
-            locked_axes
-        },
+        Interactable::new(96.0, "listen"),
+        (
+            RigidBody::Dynamic,
+            Collider::rectangle(10.0, 10.0),
+            collision_rules.bundle(GameLayer::Spirit),
+            GravityScale(0.0),
+            Friction::new(1.0),
+            LinearDamping(5.0),
+            {
+                let locked_axes = LockedAxes::ROTATION_LOCKED;
+                locked_axes.lock_translation_y();
+                locked_axes
+            },
+        ),
     ));
+    if rng.gen_bool(WATCHER_CHANCE) {
+        // Tiny eyes on the head, darting far for their size.
+        npc.insert(Gaze::new(Vec2::new(0.0, 6.0), 0.05).with_reach(20.0));
+    }
+}
+
+/// How many NPCs watch the player.
+const WATCHER_CHANCE: f64 = 0.25;
+
+/// Things the little spirits whisper when the player listens to them.
+const WHISPERS: [&str; 5] = [
+    "...is this the same room?",
+    "We were here before you.",
+    "Don't look at the face.",
+    "Keep walking. It helps.",
+    "...",
+];
+
+fn listen_to_npc(
+    trigger: Trigger<Interact>,
+    npc_query: Query<(), With<Npc>>,
+    mut text_voice: ResMut<TextVoice>,
+) {
+    if npc_query.contains(trigger.entity()) {
+        text_voice.text = WHISPERS
+            .choose(&mut rand::thread_rng())
+            .unwrap()
+            .to_string();
+    }
 }
@@ -5,21 +5,33 @@ use bevy::prelude::*;
 
 use crate::{
     game::{
-        animation::PlayerAnimation,
-        assets::{HandleMap, ImageKey},
-        movement::{Movement, MovementController, WrapWithinWindow},
+        animation::{AnimationController, Condition, Facing},
+        assets::{AsepriteKey, HandleMap, ImageKey},
+        camera::CameraTarget,
+        collision::{CollisionRole, CollisionRules, GameLayer},
+        depth::{SortLayer, YSort, FEET},
+        gaze::GazeTarget,
+        lighting::PointLight2d,
+        movement::{MoveTarget, Movement, MovementController},
+        wrap::{EdgeMode, WrapWithinWindow},
     },
     screen::Screen,
 };
 
 pub(super) fn plugin(app: &mut App) {
     app.observe(spawn_player);
+    app.observe(spawn_player_at);
     app.register_type::<Player>();
 }
 
+/// Spawns the player in the middle of the room.
 #[derive(Event, Debug)]
 pub struct SpawnPlayer;
 
+/// Spawns the player at a position in the world.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct SpawnPlayerAt(pub Vec2);
+
 #[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
 #[reflect(Component)]
 pub struct Player;
@@ -28,58 +40,80 @@ pub struct Player;
 struct PlayerBundle {
     name: Name,
     player: Player,
+    camera_target: CameraTarget,
+    gaze_target: GazeTarget,
     sprite: SpriteBundle,
-    texture_atlas: TextureAtlas,
+    y_sort: YSort,
+    light: PointLight2d,
     movement_controller: MovementController,
+    move_target: MoveTarget,
     movement: Movement,
     wrap_within_window: WrapWithinWindow,
-    player_animation: PlayerAnimation,
+    animation: AnimationController,
+    facing: Facing,
     state_scoped: StateScoped<Screen>,
     rigid_body: RigidBody,
     gravity_scale: GravityScale,
     collider: Collider,
     collider_density: ColliderDensity,
+    collision_role: CollisionRole,
+    collision_layers: CollisionLayers,
     friction: Friction,
     locked_axes: LockedAxes,
     linear_damping: LinearDamping,
 }
 
-fn spawn_player(
-    _trigger: Trigger<SpawnPlayer>,
+fn spawn_player(_trigger: Trigger<SpawnPlayer>, mut commands: Commands) {
+    commands.trigger(SpawnPlayerAt(Vec2::ZERO));
+}
+
+fn spawn_player_at(
+    trigger: Trigger<SpawnPlayerAt>,
     mut commands: Commands,
     image_handles: Res<HandleMap<ImageKey>>,
-    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    aseprite_handles: Res<HandleMap<AsepriteKey>>,
+    collision_rules: Res<CollisionRules>,
 ) {
-    // A texture atlas is a way to split one image with a grid into multiple sprites.
-    // By attaching it to a [`SpriteBundle`] and providing an index, we can specify which section of the image we want to see.
-    // We will use this to animate our player character. You can learn more about texture atlases in this example:
-    // https://github.com/bevyengine/bevy/blob/latest/examples/2d/texture_atlas.rs
-    let layout = TextureAtlasLayout::from_grid(UVec2::splat(32), 6, 2, Some(UVec2::splat(1)), None);
-    let texture_atlas_layout = texture_atlas_layouts.add(layout);
-    let player_animation = PlayerAnimation::new();
+    let (collision_role, collision_layers) = collision_rules.bundle(GameLayer::Player);
 
     commands.spawn(PlayerBundle {
         name: Name::new("Player"),
         player: Player,
+        camera_target: CameraTarget,
+        gaze_target: GazeTarget,
         sprite: SpriteBundle {
             texture: image_handles[&ImageKey::Player].clone_weak(),
             transform: Transform::from_scale(Vec2::splat(4.0).extend(1.0))
-                .with_translation(Vec3::new(0.0, 0.0, 1.0)),
+                .with_translation(trigger.event().0.extend(1.0)),
             ..Default::default()
         },
-        texture_atlas: TextureAtlas {
-            layout: texture_atlas_layout,
-            index: player_animation.get_atlas_index(),
-        },
+        y_sort: YSort::new(SortLayer::Actors).with_anchor(FEET),
+        // A soft light to find the way with when it gets dark.
+        light: PointLight2d::new(360.0, 1.0)
+            .with_falloff(1.5)
+            .with_color(Color::srgb(1.0, 0.9, 0.75)),
         movement_controller: MovementController::default(),
+        move_target: MoveTarget::default(),
         movement: Movement { speed: 420.0 },
-        wrap_within_window: WrapWithinWindow,
-        player_animation,
+        // Walking off any edge leads to the next room.
+        wrap_within_window: WrapWithinWindow::new(EdgeMode::Exit),
+        // The sheet's atlas is added along with the animation.
+        animation: AnimationController::new(
+            aseprite_handles[&AsepriteKey::Player].clone_weak(),
+            "idle",
+        )
+        .with_transition(Some("idle"), "walk", Condition::Above("speed".into(), 0.0))
+        .with_transition(Some("walk"), "idle", Condition::AtMost("speed".into(), 0.0))
+        .with_event("walk", 2, "footstep")
+        .with_event("walk", 5, "footstep"),
+        facing: Facing::default(),
         state_scoped: StateScoped(Screen::Playing),
         rigid_body: RigidBody::Kinematic,
         gravity_scale: GravityScale(0.0),
         collider: Collider::rectangle(20.0, 20.0),
         collider_density: ColliderDensity(1.5),
+        collision_role,
+        collision_layers,
         friction: Friction::new(0.8),
         locked_axes: LockedAxes::ROTATION_LOCKED.lock_translation_y(),
         linear_damping: LinearDamping(0.8),
@@ -88,7 +122,7 @@ fn spawn_player(
         //     SpriteBundle {
         //         texture: image_handles[&ImageKey::Ducky].clone_weak(),
         //         transform: Transform::from_scale(Vec2::splat(4.0).extend(1.0))
-        //             .with_translation(Vec3::new(0.0, 0.0, 1.0)),
+        //             .with_translation(trigger.event().0.extend(1.0)),
         //         ..Default::default()
         //     },
         //     TextureAtlas {
@@ -0,0 +1,299 @@
+//! Spawn rooms from [`RoomLayout`]s authored in Tiled.
+//! Chapter logic picks a room by name with [`SpawnRoom`],
+//! or has one made from a theme with [`GenerateRoom`].
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+
+use crate::{
+    game::{
+        assets::{AtlasLayouts, HandleMap, ImageKey, RoomLayouts},
+        collision::{CollisionRules, GameLayer},
+        generator::{self, RoomTheme},
+        movement::{apply_movement, Movement},
+        tiled::{RoomLayout, RoomSpawn, Tileset},
+    },
+    screen::Screen,
+    viewport::PIXEL_SIZE,
+    AppSet,
+};
+
+use super::{
+    bigface::SpawnPopUpAt,
+    level::DespawnEveryone,
+    npc::SpawnNpcAt,
+    player::{Player, SpawnPlayerAt},
+    tiles::{ItemKind, SpawnItemAt},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.observe(spawn_room)
+        .observe(generate_room)
+        .observe(spawn_room_tiles)
+        .observe(despawn_room);
+    app.init_resource::<GeneratorTileset>();
+    app.register_type::<(Room, RoomWall)>();
+    app.add_systems(
+        Update,
+        keep_out_of_walls
+            .in_set(AppSet::Update)
+            .after(apply_movement),
+    );
+}
+
+/// Spawns the room layout with the given name, e.g. `"garden"`.
+/// The player start is only used if there is no player yet.
+#[derive(Event, Debug)]
+pub struct SpawnRoom(pub String);
+
+impl SpawnRoom {
+    pub fn named(name: impl Into<String>) -> Self {
+        Self(name.into())
+    }
+}
+
+/// Spawns a procedural room. The same theme and seed always make the same room.
+#[derive(Event, Reflect, Debug, Clone, Copy, PartialEq)]
+pub struct GenerateRoom {
+    pub theme: RoomTheme,
+    pub seed: u64,
+}
+
+/// Spawns only the tiles of a room, without anything in it.
+/// Used to bring back a room whose contents were remembered.
+#[derive(Event, Debug, Clone)]
+pub struct SpawnRoomTiles(pub RoomSource);
+
+/// The tiles generated rooms are made of.
+#[derive(Resource)]
+struct GeneratorTileset(Tileset);
+
+impl FromWorld for GeneratorTileset {
+    fn from_world(world: &mut World) -> Self {
+        let image = world.resource::<HandleMap<ImageKey>>()[&ImageKey::Tiles].clone_weak();
+        let layout = world.resource::<AtlasLayouts>()[&ImageKey::Tiles].clone_weak();
+        Self(Tileset {
+            image,
+            layout,
+            tile_size: Vec2::splat(16.0),
+        })
+    }
+}
+
+/// The parent of a room's tiles.
+#[derive(Component, Reflect, Debug, Clone, PartialEq)]
+#[reflect(Component)]
+pub struct Room(pub RoomSource);
+
+/// Where a room's tiles came from.
+#[derive(Reflect, Debug, Clone, PartialEq)]
+pub enum RoomSource {
+    /// A layout from [`RoomLayouts`].
+    Authored(String),
+    Generated(GenerateRoom),
+}
+
+impl RoomSource {
+    fn name(&self) -> String {
+        match self {
+            RoomSource::Authored(name) => name.clone(),
+            RoomSource::Generated(GenerateRoom { theme, seed }) => format!("{theme:?} {seed}"),
+        }
+    }
+}
+
+/// A tile that blocks movement.
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub struct RoomWall {
+    /// Half the tile's size in world units.
+    pub half_size: Vec2,
+}
+
+/// Every room is this many pixels across: 20 by 12 tiles of 16 pixels.
+/// A little taller than the canvas, since 180 pixels isn't a whole number of tiles.
+pub const ROOM_PIXELS: UVec2 = UVec2::new(320, 192);
+/// The size of every room in world units.
+pub const ROOM_SIZE: Vec2 = Vec2::new(
+    ROOM_PIXELS.x as f32 * PIXEL_SIZE,
+    ROOM_PIXELS.y as f32 * PIXEL_SIZE,
+);
+
+/// Tiles are drawn below everything else, each layer a little above the last.
+const ROOM_DEPTH: f32 = -10.0;
+
+/// About half the width of anything moved by [`Movement`], in world units.
+const MOVER_RADIUS: f32 = 32.0;
+
+fn spawn_room(
+    trigger: Trigger<SpawnRoom>,
+    mut commands: Commands,
+    room_layouts: Res<RoomLayouts>,
+    layouts: Res<Assets<RoomLayout>>,
+    player_query: Query<(), With<Player>>,
+    collision_rules: Res<CollisionRules>,
+) {
+    let name = &trigger.event().0;
+    let Some(layout) = room_layouts
+        .get(name)
+        .and_then(|handle| layouts.get(handle))
+    else {
+        warn!("There is no room layout named {name:?}");
+        return;
+    };
+    spawn_tiles(
+        &mut commands,
+        RoomSource::Authored(name.clone()),
+        layout,
+        &collision_rules,
+    );
+    spawn_contents(&mut commands, layout, player_query.is_empty());
+}
+
+fn generate_room(
+    trigger: Trigger<GenerateRoom>,
+    mut commands: Commands,
+    tileset: Res<GeneratorTileset>,
+    player_query: Query<(), With<Player>>,
+    collision_rules: Res<CollisionRules>,
+) {
+    let room = *trigger.event();
+    let layout = generator::generate(room.theme, room.seed, tileset.0.clone());
+    spawn_tiles(
+        &mut commands,
+        RoomSource::Generated(room),
+        &layout,
+        &collision_rules,
+    );
+    spawn_contents(&mut commands, &layout, player_query.is_empty());
+}
+
+fn spawn_room_tiles(
+    trigger: Trigger<SpawnRoomTiles>,
+    mut commands: Commands,
+    room_layouts: Res<RoomLayouts>,
+    layouts: Res<Assets<RoomLayout>>,
+    tileset: Res<GeneratorTileset>,
+    collision_rules: Res<CollisionRules>,
+) {
+    let source = trigger.event().0.clone();
+    match &source {
+        RoomSource::Authored(name) => {
+            if let Some(layout) = room_layouts
+                .get(name)
+                .and_then(|handle| layouts.get(handle))
+            {
+                spawn_tiles(&mut commands, source.clone(), layout, &collision_rules);
+            }
+        }
+        RoomSource::Generated(room) => {
+            let layout = generator::generate(room.theme, room.seed, tileset.0.clone());
+            spawn_tiles(&mut commands, source.clone(), &layout, &collision_rules);
+        }
+    }
+}
+
+/// Spawn the room's tiles under a [`Room`].
+fn spawn_tiles(
+    commands: &mut Commands,
+    source: RoomSource,
+    layout: &RoomLayout,
+    collision_rules: &CollisionRules,
+) {
+    commands
+        .spawn((
+            Name::new(format!("Room {}", source.name())),
+            Room(source),
+            SpatialBundle::default(),
+            StateScoped(Screen::Playing),
+        ))
+        .with_children(|children| {
+            for (depth, layer) in layout.layers.iter().enumerate() {
+                for tile in &layer.tiles {
+                    let tileset = &layout.tilesets[tile.tileset];
+                    let translation = tile.position.extend(ROOM_DEPTH + depth as f32 * 0.1);
+                    let mut entity = children.spawn((
+                        Name::new(format!("{} Tile", layer.name)),
+                        SpriteBundle {
+                            texture: tileset.image.clone_weak(),
+                            sprite: Sprite {
+                                flip_x: tile.flip_x,
+                                flip_y: tile.flip_y,
+                                ..default()
+                            },
+                            transform: Transform::from_scale(Vec2::splat(4.0).extend(1.0))
+                                .with_translation(translation),
+                            ..default()
+                        },
+                        TextureAtlas {
+                            layout: tileset.layout.clone_weak(),
+                            index: tile.index,
+                        },
+                    ));
+                    if layer.solid {
+                        entity.insert((
+                            RoomWall {
+                                half_size: tileset.tile_size * PIXEL_SIZE / 2.0,
+                            },
+                            RigidBody::Static,
+                            Collider::rectangle(tileset.tile_size.x, tileset.tile_size.y),
+                            collision_rules.bundle(GameLayer::Wall),
+                        ));
+                    }
+                }
+            }
+        });
+}
+
+/// Trigger spawns for everything in the room.
+fn spawn_contents(commands: &mut Commands, layout: &RoomLayout, spawn_player: bool) {
+    let mut rng = rand::thread_rng();
+    for spawn in &layout.spawns {
+        match *spawn {
+            RoomSpawn::Player(position) => {
+                if spawn_player {
+                    commands.trigger(SpawnPlayerAt(position));
+                }
+            }
+            RoomSpawn::Npc(position) => commands.trigger(SpawnNpcAt(position)),
+            RoomSpawn::Item(position, kind) => {
+                let kind = kind.unwrap_or_else(|| *ItemKind::ELEMENTS.choose(&mut rng).unwrap());
+                commands.trigger(SpawnItemAt { kind, position });
+            }
+            RoomSpawn::Face(position) => commands.trigger(SpawnPopUpAt(position)),
+        }
+    }
+}
+
+fn despawn_room(
+    _trigger: Trigger<DespawnEveryone>,
+    mut commands: Commands,
+    room_query: Query<Entity, With<Room>>,
+) {
+    for entity in &room_query {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Push anything that walked into a wall back out the shortest way.
+/// Walls also have colliders, but transform-driven movers aren't stopped by them.
+fn keep_out_of_walls(
+    wall_query: Query<(&GlobalTransform, &RoomWall)>,
+    mut mover_query: Query<&mut Transform, With<Movement>>,
+) {
+    for mut transform in &mut mover_query {
+        for (wall_transform, wall) in &wall_query {
+            let offset = transform.translation.xy() - wall_transform.translation().xy();
+            let overlap = wall.half_size + Vec2::splat(MOVER_RADIUS) - offset.abs();
+            if overlap.x <= 0.0 || overlap.y <= 0.0 {
+                continue;
+            }
+            if overlap.x < overlap.y {
+                transform.translation.x += overlap.x * offset.x.signum();
+            } else {
+                transform.translation.y += overlap.y * offset.y.signum();
+            }
+        }
+    }
+}
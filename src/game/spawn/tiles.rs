@@ -1,64 +1,168 @@
+use avian2d::prelude::*;
 use bevy::prelude::*;
-use rand::Rng;
+use rand::{seq::SliceRandom, Rng};
 
-use crate::game::assets::{HandleMap, ImageKey};
+use crate::game::{
+    assets::{AtlasLayouts, HandleMap, ImageKey},
+    collision::{CollisionRules, GameLayer},
+    depth::{SortLayer, YSort},
+    dream::DreamSprite,
+    interaction::Interactable,
+};
+
+use super::spawn_target;
 
 pub(super) fn plugin(app: &mut App) {
-    app.observe(spawn_item).register_type::<Item>();
+    app.observe(spawn_item)
+        .observe(spawn_item_at)
+        .register_type::<Item>();
 }
 
-#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
 #[reflect(Component)]
-pub struct Item;
+pub struct Item(pub ItemKind);
+
+/// Every collectible item, named after the sprite at its atlas index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum ItemKind {
+    // `elementos.png`
+    Ring,
+    Ash,
+    Stars,
+    Thread,
+    Fern,
+    Cloud,
+    Petals,
+    Seeds,
+    Stone,
+    // `Pelos.png`
+    Wisps,
+    Arc,
+    Strands,
+    Snip,
+    Swoop,
+    Curl,
+    Lock,
+    Tangle,
+    Crumbs,
+}
+
+impl ItemKind {
+    /// Items on the `elementos` sheet, in atlas order.
+    pub const ELEMENTS: [ItemKind; 9] = [
+        ItemKind::Ring,
+        ItemKind::Ash,
+        ItemKind::Stars,
+        ItemKind::Thread,
+        ItemKind::Fern,
+        ItemKind::Cloud,
+        ItemKind::Petals,
+        ItemKind::Seeds,
+        ItemKind::Stone,
+    ];
+
+    /// Items on the `Pelos` sheet, in atlas order.
+    pub const HAIRS: [ItemKind; 9] = [
+        ItemKind::Wisps,
+        ItemKind::Arc,
+        ItemKind::Strands,
+        ItemKind::Snip,
+        ItemKind::Swoop,
+        ItemKind::Curl,
+        ItemKind::Lock,
+        ItemKind::Tangle,
+        ItemKind::Crumbs,
+    ];
+
+    /// The sheet this item is drawn from.
+    pub fn image_key(self) -> ImageKey {
+        if Self::ELEMENTS.contains(&self) {
+            ImageKey::Elements
+        } else {
+            ImageKey::Elements2
+        }
+    }
+
+    /// The item with the given name, e.g. `"Ring"`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ELEMENTS
+            .into_iter()
+            .chain(Self::HAIRS)
+            .find(|kind| format!("{kind:?}") == name)
+    }
 
+    /// Return sprite index in the atlas.
+    pub fn atlas_index(self) -> usize {
+        Self::ELEMENTS
+            .iter()
+            .chain(&Self::HAIRS)
+            .position(|&kind| kind == self)
+            .unwrap()
+            % 9
+    }
+}
+
+/// Spawns one item from each sheet at random.
 #[derive(Event, Debug)]
 pub struct SpawnItem;
 
-fn spawn_item(
-    _trigger: Trigger<SpawnItem>,
-    mut commands: Commands,
-    image_handles: Res<HandleMap<ImageKey>>,
-    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
-) {
-    // Create a texture atlas
-    let layout = TextureAtlasLayout::from_grid(UVec2::splat(16), 3, 3, Some(UVec2::splat(1)), None);
-    let texture_atlas_layout = texture_atlas_layouts.add(layout);
+/// Spawns an item of the given kind at a position in the world.
+/// Trigger it on an entity to build the item there instead of on a new one.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct SpawnItemAt {
+    pub kind: ItemKind,
+    pub position: Vec2,
+}
+
+fn spawn_item(_trigger: Trigger<SpawnItem>, mut commands: Commands) {
     let mut rng = rand::thread_rng();
     let x = rng.gen_range(-500.0..500.0); // Adjust the range as needed
     let y = rng.gen_range(-500.0..500.0); // Adjust the range as needed
-    let translation = Vec3::new(x, y, 0.0);
-    //let sprite_index = rng.gen_range(0..9); // Random index from 0 to 8
-
-    commands.spawn((
-        Name::new("Item"),
-        Item,
-        SpriteBundle {
-            texture: image_handles[&ImageKey::Elements].clone_weak(),
-            transform: Transform::from_scale(Vec2::splat(4.0).extend(1.0))
-                .with_translation(translation),
-            ..Default::default()
-        },
-        TextureAtlas {
-            layout: texture_atlas_layout.clone(),
-            index: rng.gen_range(0..9),
-        },
-    ));
+    commands.trigger(SpawnItemAt {
+        kind: *ItemKind::ELEMENTS.choose(&mut rng).unwrap(),
+        position: Vec2::new(x, y),
+    });
 
     let x2 = rng.gen_range(-800.0..800.0); // Adjust the range as needed
     let y2 = rng.gen_range(-800.0..800.0); // Adjust the range as needed
-    let translation2 = Vec3::new(x2, y2, 0.0);
-    commands.spawn((
-        Name::new("Hair"),
-        Item,
+    commands.trigger(SpawnItemAt {
+        kind: *ItemKind::HAIRS.choose(&mut rng).unwrap(),
+        position: Vec2::new(x2, y2),
+    });
+}
+
+fn spawn_item_at(
+    trigger: Trigger<SpawnItemAt>,
+    mut commands: Commands,
+    image_handles: Res<HandleMap<ImageKey>>,
+    atlas_layouts: Res<AtlasLayouts>,
+    collision_rules: Res<CollisionRules>,
+) {
+    let SpawnItemAt { kind, position } = *trigger.event();
+    let name = if ItemKind::HAIRS.contains(&kind) {
+        "Hair"
+    } else {
+        "Item"
+    };
+
+    spawn_target(&mut commands, trigger.entity()).insert((
+        Name::new(name),
+        Item(kind),
         SpriteBundle {
-            texture: image_handles[&ImageKey::Elements2].clone_weak(),
+            texture: image_handles[&kind.image_key()].clone_weak(),
             transform: Transform::from_scale(Vec2::splat(4.0).extend(1.0))
-                .with_translation(translation2),
+                .with_translation(position.extend(0.0)),
             ..Default::default()
         },
+        YSort::new(SortLayer::Ground),
         TextureAtlas {
-            layout: texture_atlas_layout.clone(),
-            index: rng.gen_range(0..9),
+            layout: atlas_layouts[&kind.image_key()].clone_weak(),
+            index: kind.atlas_index(),
         },
+        RigidBody::Static,
+        Collider::rectangle(12.0, 12.0),
+        collision_rules.bundle(GameLayer::Item),
+        Interactable::new(80.0, "take"),
+        DreamSprite,
     ));
 }
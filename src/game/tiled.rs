@@ -0,0 +1,347 @@
+//! Room layouts made in the [Tiled](https://www.mapeditor.org/) map editor.
+//! Save maps as JSON with the `.tmj` extension and embed their tilesets.
+//! Maps must be [`ROOM_PIXELS`] in size, e.g. 20 by 12 tiles of 16 pixels.
+//! Tile layers become floor and decoration, and tile layers with a `solid` property are walls.
+//! Objects whose class is `Player`, `Npc`, `Item` or `Face` mark where those are spawned.
+//! An `Item` can pick its kind with a `kind` string property, e.g. `Ring`.
+
+use std::fmt;
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+};
+use serde::Deserialize;
+
+use crate::{
+    game::spawn::{room::ROOM_PIXELS, tiles::ItemKind},
+    viewport::PIXEL_SIZE,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_asset::<RoomLayout>();
+    app.init_asset_loader::<TiledLoader>();
+}
+
+/// A room's tiles and where its entities go. Positions are in world units,
+/// with the room centered on the origin.
+#[derive(Asset, TypePath, Debug)]
+pub struct RoomLayout {
+    pub tilesets: Vec<Tileset>,
+    /// Tile layers from bottom to top.
+    pub layers: Vec<TileLayer>,
+    pub spawns: Vec<RoomSpawn>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Tileset {
+    pub image: Handle<Image>,
+    pub layout: Handle<TextureAtlasLayout>,
+    /// The size of one tile in the image, in pixels.
+    pub tile_size: Vec2,
+}
+
+#[derive(Debug)]
+pub struct TileLayer {
+    pub name: String,
+    /// Tiles on this layer block movement.
+    pub solid: bool,
+    pub tiles: Vec<Tile>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Tile {
+    /// The center of the tile.
+    pub position: Vec2,
+    /// Index into [`RoomLayout::tilesets`].
+    pub tileset: usize,
+    /// Index into the tileset's atlas.
+    pub index: usize,
+    pub flip_x: bool,
+    pub flip_y: bool,
+}
+
+/// Something to spawn when the room is entered.
+#[derive(Debug, Clone, Copy)]
+pub enum RoomSpawn {
+    Player(Vec2),
+    Npc(Vec2),
+    /// Without a kind, a random one is picked.
+    Item(Vec2, Option<ItemKind>),
+    Face(Vec2),
+}
+
+/// The parts of a Tiled JSON map we use.
+#[derive(Deserialize)]
+struct MapJson {
+    width: u32,
+    height: u32,
+    tilewidth: u32,
+    tileheight: u32,
+    layers: Vec<LayerJson>,
+    #[serde(default)]
+    tilesets: Vec<TilesetJson>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum LayerJson {
+    TileLayer {
+        name: String,
+        width: u32,
+        #[serde(default)]
+        data: Vec<u32>,
+        encoding: Option<String>,
+        #[serde(default)]
+        properties: Vec<PropertyJson>,
+    },
+    ObjectGroup {
+        #[serde(default)]
+        objects: Vec<ObjectJson>,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize)]
+struct ObjectJson {
+    /// Called "class" in some Tiled versions.
+    #[serde(rename = "type", alias = "class", default)]
+    class: String,
+    x: f32,
+    y: f32,
+    #[serde(default)]
+    width: f32,
+    #[serde(default)]
+    height: f32,
+    #[serde(default)]
+    properties: Vec<PropertyJson>,
+}
+
+#[derive(Deserialize)]
+struct PropertyJson {
+    name: String,
+    value: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+struct TilesetJson {
+    firstgid: u32,
+    /// Only set for external tilesets, which aren't supported.
+    source: Option<String>,
+    #[serde(default)]
+    image: String,
+    #[serde(default)]
+    tilewidth: u32,
+    #[serde(default)]
+    tileheight: u32,
+    #[serde(default)]
+    columns: u32,
+    #[serde(default)]
+    tilecount: u32,
+    #[serde(default)]
+    margin: u32,
+    #[serde(default)]
+    spacing: u32,
+}
+
+fn property<'a>(properties: &'a [PropertyJson], name: &str) -> Option<&'a serde_json::Value> {
+    properties
+        .iter()
+        .find(|property| property.name == name)
+        .map(|property| &property.value)
+}
+
+/// Tiled stores flips in the top bits of each tile id.
+const FLIP_X: u32 = 0x8000_0000;
+const FLIP_Y: u32 = 0x4000_0000;
+const FLIP_DIAGONAL: u32 = 0x2000_0000;
+
+#[derive(Default)]
+struct TiledLoader;
+
+impl AssetLoader for TiledLoader {
+    type Asset = RoomLayout;
+    type Settings = ();
+    type Error = TiledError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext<'_>,
+    ) -> Result<RoomLayout, TiledError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let json: MapJson = serde_json::from_slice(&bytes)?;
+
+        let map_pixels = UVec2::new(json.width * json.tilewidth, json.height * json.tileheight);
+        // Rooms wrap at their edges, so they must all fill the same bounds.
+        if map_pixels != ROOM_PIXELS {
+            return Err(TiledError::WrongSize(map_pixels));
+        }
+        let map_size = map_pixels.as_vec2();
+        // Tiled counts pixels down from the top-left corner.
+        let to_world =
+            |x: f32, y: f32| Vec2::new(x - map_size.x / 2.0, map_size.y / 2.0 - y) * PIXEL_SIZE;
+
+        let mut tilesets = Vec::with_capacity(json.tilesets.len());
+        for (i, tileset) in json.tilesets.iter().enumerate() {
+            if let Some(source) = &tileset.source {
+                return Err(TiledError::ExternalTileset(source.clone()));
+            }
+            let rows = tileset.tilecount.div_ceil(tileset.columns.max(1));
+            let layout = TextureAtlasLayout::from_grid(
+                UVec2::new(tileset.tilewidth, tileset.tileheight),
+                tileset.columns,
+                rows,
+                Some(UVec2::splat(tileset.spacing)),
+                Some(UVec2::splat(tileset.margin)),
+            );
+            let image_path = load_context
+                .asset_path()
+                .resolve_embed(&tileset.image)
+                .map_err(|_| TiledError::InvalidImagePath(tileset.image.clone()))?;
+            tilesets.push(Tileset {
+                image: load_context.load(image_path),
+                layout: load_context.add_labeled_asset(format!("tileset{i}"), layout),
+                tile_size: Vec2::new(tileset.tilewidth as f32, tileset.tileheight as f32),
+            });
+        }
+
+        let mut layers = Vec::new();
+        let mut spawns = Vec::new();
+        for layer in json.layers {
+            match layer {
+                LayerJson::TileLayer {
+                    name,
+                    width,
+                    data,
+                    encoding,
+                    properties,
+                } => {
+                    if let Some(encoding) = encoding.filter(|encoding| encoding != "csv") {
+                        return Err(TiledError::UnsupportedEncoding(encoding));
+                    }
+                    let mut tiles = Vec::new();
+                    for (i, &raw) in data.iter().enumerate() {
+                        let gid = raw & !(FLIP_X | FLIP_Y | FLIP_DIAGONAL);
+                        // Zero is an empty cell.
+                        if gid == 0 {
+                            continue;
+                        }
+                        let tileset = json
+                            .tilesets
+                            .iter()
+                            .rposition(|tileset| tileset.firstgid <= gid)
+                            .ok_or(TiledError::UnknownTile(gid))?;
+                        let (column, row) = (i as u32 % width, i as u32 / width);
+                        tiles.push(Tile {
+                            position: to_world(
+                                (column as f32 + 0.5) * json.tilewidth as f32,
+                                (row as f32 + 0.5) * json.tileheight as f32,
+                            ),
+                            tileset,
+                            index: (gid - json.tilesets[tileset].firstgid) as usize,
+                            flip_x: raw & FLIP_X != 0,
+                            flip_y: raw & FLIP_Y != 0,
+                        });
+                    }
+                    let solid = property(&properties, "solid")
+                        .and_then(serde_json::Value::as_bool)
+                        .unwrap_or(false);
+                    layers.push(TileLayer { name, solid, tiles });
+                }
+                LayerJson::ObjectGroup { objects } => {
+                    for object in objects {
+                        let position = to_world(
+                            object.x + object.width / 2.0,
+                            object.y + object.height / 2.0,
+                        );
+                        spawns.push(match object.class.as_str() {
+                            "Player" => RoomSpawn::Player(position),
+                            "Npc" => RoomSpawn::Npc(position),
+                            "Face" => RoomSpawn::Face(position),
+                            "Item" => {
+                                let kind = match property(&object.properties, "kind")
+                                    .and_then(serde_json::Value::as_str)
+                                {
+                                    Some(name) => Some(
+                                        ItemKind::from_name(name)
+                                            .ok_or_else(|| TiledError::UnknownItem(name.into()))?,
+                                    ),
+                                    None => None,
+                                };
+                                RoomSpawn::Item(position, kind)
+                            }
+                            class => return Err(TiledError::UnknownObject(class.into())),
+                        });
+                    }
+                }
+                LayerJson::Other => {}
+            }
+        }
+
+        Ok(RoomLayout {
+            tilesets,
+            layers,
+            spawns,
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["tmj"]
+    }
+}
+
+#[derive(Debug)]
+pub enum TiledError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    ExternalTileset(String),
+    InvalidImagePath(String),
+    UnsupportedEncoding(String),
+    UnknownTile(u32),
+    UnknownObject(String),
+    UnknownItem(String),
+    WrongSize(UVec2),
+}
+
+impl fmt::Display for TiledError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TiledError::Io(error) => write!(f, "could not read Tiled map: {error}"),
+            TiledError::Json(error) => write!(f, "invalid Tiled map: {error}"),
+            TiledError::ExternalTileset(source) => {
+                write!(f, "tileset {source:?} must be embedded in the map")
+            }
+            TiledError::InvalidImagePath(path) => write!(f, "invalid tileset image path {path:?}"),
+            TiledError::UnsupportedEncoding(encoding) => {
+                write!(
+                    f,
+                    "tile layer encoding {encoding:?} is not supported, use CSV"
+                )
+            }
+            TiledError::UnknownTile(gid) => write!(f, "tile {gid} is not in any tileset"),
+            TiledError::UnknownObject(class) => write!(f, "unknown object class {class:?}"),
+            TiledError::UnknownItem(name) => write!(f, "unknown item kind {name:?}"),
+            TiledError::WrongSize(size) => {
+                write!(f, "map is {size} pixels, but rooms are {ROOM_PIXELS}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TiledError {}
+
+impl From<std::io::Error> for TiledError {
+    fn from(error: std::io::Error) -> Self {
+        TiledError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for TiledError {
+    fn from(error: serde_json::Error) -> Self {
+        TiledError::Json(error)
+    }
+}
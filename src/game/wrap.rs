@@ -0,0 +1,245 @@
+//! Keep things inside the current [`RoomBounds`].
+//! Each axis of a [`WrapWithinWindow`] either wraps around, stops at the edge,
+//! or wraps and counts as leaving the room.
+//! Sprites close to a wrapping edge get a ghost copy on the opposite side,
+//! so crossing it reads as walking around a torus instead of popping across.
+
+use bevy::{prelude::*, transform::TransformSystem};
+
+use crate::{
+    game::{depth::YSort, dream::DreamSprite, movement::apply_movement, spawn::level::RoomBounds},
+    screen::Screen,
+    AppSet,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<(WrapWithinWindow, WrapGhost)>();
+    app.add_systems(
+        Update,
+        wrap_within_window
+            .in_set(AppSet::Update)
+            .after(apply_movement),
+    );
+    // Ghosts copy their source after everything else has updated it.
+    app.add_systems(
+        PostUpdate,
+        (despawn_orphan_ghosts, update_wrap_ghosts)
+            .chain()
+            .before(TransformSystem::TransformPropagate),
+    );
+}
+
+/// What happens when an entity reaches the edge of the room along one axis.
+#[derive(Reflect, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EdgeMode {
+    /// Come back in on the opposite side.
+    #[default]
+    Wrap,
+    /// Stop at the edge.
+    Clamp,
+    /// Wrap, and trigger [`ExitRoom`] on the entity.
+    Exit,
+}
+
+/// Keep the entity inside the current [`RoomBounds`], with a separate [`EdgeMode`] per axis.
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[reflect(Component)]
+pub struct WrapWithinWindow {
+    pub x: EdgeMode,
+    pub y: EdgeMode,
+}
+
+impl WrapWithinWindow {
+    /// The same mode on both axes.
+    pub const fn new(mode: EdgeMode) -> Self {
+        Self { x: mode, y: mode }
+    }
+}
+
+/// Triggered on an entity when it leaves the room across an [`EdgeMode::Exit`] edge.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ExitRoom {
+    /// Which way it left, e.g. `IVec2::X` across the right edge.
+    pub direction: IVec2,
+}
+
+pub(super) fn wrap_within_window(
+    mut commands: Commands,
+    room: Res<RoomBounds>,
+    mut wrap_query: Query<(Entity, &WrapWithinWindow, &mut Transform)>,
+) {
+    let Rect { min, max } = room.0;
+    let size = room.0.size();
+    for (entity, wrap, mut transform) in &mut wrap_query {
+        let position = transform.translation.xy();
+        let mut wrapped = position;
+        let mut exited = IVec2::ZERO;
+        for (axis, mode) in [(0, wrap.x), (1, wrap.y)] {
+            let value = position[axis];
+            if value >= min[axis] && value < max[axis] {
+                continue;
+            }
+            wrapped[axis] = match mode {
+                EdgeMode::Clamp => value.clamp(min[axis], max[axis]),
+                EdgeMode::Wrap | EdgeMode::Exit => {
+                    (value - min[axis]).rem_euclid(size[axis]) + min[axis]
+                }
+            };
+            if mode == EdgeMode::Exit {
+                exited[axis] = if value < min[axis] { -1 } else { 1 };
+            }
+        }
+
+        if exited != IVec2::ZERO {
+            commands.trigger_targets(ExitRoom { direction: exited }, entity);
+        }
+        if wrapped != position {
+            transform.translation = wrapped.extend(transform.translation.z);
+        }
+    }
+}
+
+/// A copy of a wrapping sprite, drawn across the room from it.
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+pub struct WrapGhost {
+    source: Entity,
+}
+
+/// The ghosts of a sprite: across the x edge, the y edge, and the corner.
+#[derive(Component, Default)]
+pub(super) struct Ghosts([Option<Entity>; 3]);
+
+/// How close to an edge a sprite gets a ghost.
+/// Half the size of the largest sprite that wraps.
+const GHOST_MARGIN: f32 = 64.0;
+
+pub(super) fn update_wrap_ghosts(
+    mut commands: Commands,
+    room: Res<RoomBounds>,
+    mut source_query: Query<
+        (
+            Entity,
+            &WrapWithinWindow,
+            &Transform,
+            &Sprite,
+            &Handle<Image>,
+            Option<&TextureAtlas>,
+            Option<&mut Ghosts>,
+            Has<DreamSprite>,
+            Option<&YSort>,
+        ),
+        Without<WrapGhost>,
+    >,
+    mut ghost_query: Query<
+        (
+            &mut Transform,
+            &mut Sprite,
+            &mut Handle<Image>,
+            Option<&mut TextureAtlas>,
+        ),
+        With<WrapGhost>,
+    >,
+) {
+    let Rect { min, max } = room.0;
+    let size = room.0.size();
+    for (source, wrap, transform, sprite, texture, atlas, ghosts, dream, y_sort) in
+        &mut source_query
+    {
+        let position = transform.translation.xy();
+        let shift = |axis: usize, mode: EdgeMode| {
+            if mode == EdgeMode::Clamp {
+                0.0
+            } else if position[axis] - min[axis] < GHOST_MARGIN {
+                size[axis]
+            } else if max[axis] - position[axis] < GHOST_MARGIN {
+                -size[axis]
+            } else {
+                0.0
+            }
+        };
+        let shift = Vec2::new(shift(0, wrap.x), shift(1, wrap.y));
+        let offsets = [
+            (shift.x != 0.0).then(|| Vec2::new(shift.x, 0.0)),
+            (shift.y != 0.0).then(|| Vec2::new(0.0, shift.y)),
+            (shift.x != 0.0 && shift.y != 0.0).then_some(shift),
+        ];
+
+        let mut new_ghosts = None;
+        let slots = match ghosts {
+            Some(ghosts) => &mut ghosts.into_inner().0,
+            None if offsets.iter().all(Option::is_none) => continue,
+            None => &mut new_ghosts.insert(Ghosts::default()).0,
+        };
+
+        for (slot, offset) in slots.iter_mut().zip(offsets) {
+            let Some(offset) = offset else {
+                if let Some(ghost) = slot.take() {
+                    commands.entity(ghost).despawn_recursive();
+                }
+                continue;
+            };
+            let ghost_transform = Transform {
+                translation: transform.translation + offset.extend(0.0),
+                ..*transform
+            };
+
+            if let Some(Ok((
+                mut ghost_transform_mut,
+                mut ghost_sprite,
+                mut ghost_texture,
+                ghost_atlas,
+            ))) = slot.map(|ghost| ghost_query.get_mut(ghost))
+            {
+                *ghost_transform_mut = ghost_transform;
+                ghost_sprite.clone_from(sprite);
+                if *ghost_texture != *texture {
+                    *ghost_texture = texture.clone_weak();
+                }
+                if let (Some(mut ghost_atlas), Some(atlas)) = (ghost_atlas, atlas) {
+                    ghost_atlas.clone_from(atlas);
+                }
+                continue;
+            }
+
+            let mut ghost = commands.spawn((
+                Name::new("Wrap Ghost"),
+                WrapGhost { source },
+                SpriteBundle {
+                    sprite: sprite.clone(),
+                    texture: texture.clone_weak(),
+                    transform: ghost_transform,
+                    ..default()
+                },
+                StateScoped(Screen::Playing),
+            ));
+            if let Some(atlas) = atlas {
+                ghost.insert(atlas.clone());
+            }
+            if dream {
+                ghost.insert(DreamSprite);
+            }
+            // Sort by where the ghost is drawn, not by its source.
+            if let Some(y_sort) = y_sort {
+                ghost.insert(*y_sort);
+            }
+            *slot = Some(ghost.id());
+        }
+
+        if let Some(ghosts) = new_ghosts {
+            commands.entity(source).insert(ghosts);
+        }
+    }
+}
+
+fn despawn_orphan_ghosts(
+    mut commands: Commands,
+    ghost_query: Query<(Entity, &WrapGhost)>,
+    source_query: Query<(), With<WrapWithinWindow>>,
+) {
+    for (entity, ghost) in &ghost_query {
+        if !source_query.contains(ghost.source) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
@@ -0,0 +1,224 @@
+//! Map raw keyboard and gamepad input to game actions.
+//! Gameplay and screens read [`ActionState`] instead of raw [`KeyCode`]s,
+//! so every action can be rebound from the settings screen.
+
+use std::collections::HashMap;
+
+use bevy::{input::InputSystem, prelude::*, ui::UiSystem, utils::HashSet};
+use serde::{Deserialize, Serialize};
+
+use crate::settings::Settings;
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<(ActionState, Bindings)>();
+    app.init_resource::<ActionState>();
+    // Update before UI focus so menu navigation sees this frame's actions.
+    app.add_systems(
+        PreUpdate,
+        update_action_state
+            .after(InputSystem)
+            .before(UiSystem::Focus),
+    );
+}
+
+/// Everything the player can do, independent of which key does it.
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Interact,
+    Pause,
+    Skip,
+}
+
+impl Action {
+    pub const ALL: [Action; 7] = [
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Interact,
+        Action::Pause,
+        Action::Skip,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::MoveUp => "Move Up",
+            Action::MoveDown => "Move Down",
+            Action::MoveLeft => "Move Left",
+            Action::MoveRight => "Move Right",
+            Action::Interact => "Interact",
+            Action::Pause => "Pause",
+            Action::Skip => "Skip",
+        }
+    }
+}
+
+/// A single physical input that can trigger an action.
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Binding {
+    Key(KeyCode),
+    GamepadButton(GamepadButtonType),
+}
+
+impl Binding {
+    /// A short, human-readable name, e.g. "E" or "Up".
+    pub fn label(self) -> String {
+        match self {
+            Binding::Key(key) => {
+                let name = format!("{key:?}");
+                ["Key", "Digit", "Arrow"]
+                    .into_iter()
+                    .find_map(|prefix| name.strip_prefix(prefix))
+                    .map_or(name.clone(), str::to_string)
+            }
+            Binding::GamepadButton(button) => format!("Pad {button:?}"),
+        }
+    }
+}
+
+/// Which bindings trigger each action. An action can have any number of bindings.
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Bindings(HashMap<Action, Vec<Binding>>);
+
+impl Default for Bindings {
+    fn default() -> Self {
+        use GamepadButtonType::*;
+        use KeyCode::*;
+        let bind = |keys: &[KeyCode], buttons: &[GamepadButtonType]| {
+            let keys = keys.iter().copied().map(Binding::Key);
+            let buttons = buttons.iter().copied().map(Binding::GamepadButton);
+            keys.chain(buttons).collect()
+        };
+        Self(HashMap::from([
+            (Action::MoveUp, bind(&[KeyW, ArrowUp], &[DPadUp])),
+            (Action::MoveDown, bind(&[KeyS, ArrowDown], &[DPadDown])),
+            (Action::MoveLeft, bind(&[KeyA, ArrowLeft], &[DPadLeft])),
+            (Action::MoveRight, bind(&[KeyD, ArrowRight], &[DPadRight])),
+            (Action::Interact, bind(&[KeyE], &[South])),
+            (Action::Pause, bind(&[Escape], &[Start])),
+            (Action::Skip, bind(&[Space, Enter], &[South, Start])),
+        ]))
+    }
+}
+
+impl Bindings {
+    pub fn get(&self, action: Action) -> &[Binding] {
+        self.0.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Add the binding to the action, or remove it if the action already has it.
+    pub fn toggle(&mut self, action: Action, binding: Binding) {
+        let bindings = self.0.entry(action).or_default();
+        match bindings.iter().position(|&bound| bound == binding) {
+            Some(index) => {
+                bindings.remove(index);
+            }
+            None => bindings.push(binding),
+        }
+    }
+
+    /// The bindings of an action joined for display, e.g. "W, Up".
+    pub fn label(&self, action: Action) -> String {
+        let labels: Vec<_> = self
+            .get(action)
+            .iter()
+            .map(|binding| binding.label())
+            .collect();
+        if labels.is_empty() {
+            "-".to_string()
+        } else {
+            labels.join(", ")
+        }
+    }
+}
+
+/// Which actions are held, which were pressed this frame, and where the left stick points.
+#[derive(Resource, Reflect, Default, Debug)]
+#[reflect(Resource)]
+pub struct ActionState {
+    pressed: HashSet<Action>,
+    just_pressed: HashSet<Action>,
+    stick: Vec2,
+}
+
+impl ActionState {
+    /// The left stick of whichever gamepad is pushed the furthest.
+    /// Its length is between 0 and 1, so it can be used as-is for analog movement.
+    pub fn stick(&self) -> Vec2 {
+        self.stick
+    }
+
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.just_pressed.contains(&action)
+    }
+}
+
+fn update_action_state(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    settings: Res<Settings>,
+    mut action_state: ResMut<ActionState>,
+) {
+    let ActionState {
+        pressed,
+        just_pressed,
+        stick,
+    } = &mut *action_state;
+    pressed.clear();
+    just_pressed.clear();
+
+    *stick = gamepads
+        .iter()
+        .map(|gamepad| {
+            let axis = |axis_type| {
+                gamepad_axes
+                    .get(GamepadAxis::new(gamepad, axis_type))
+                    .unwrap_or_default()
+            };
+            Vec2::new(
+                axis(GamepadAxisType::LeftStickX),
+                axis(GamepadAxisType::LeftStickY),
+            )
+            .clamp_length_max(1.0)
+        })
+        .max_by(|a, b| a.length_squared().total_cmp(&b.length_squared()))
+        .unwrap_or_default();
+
+    for action in Action::ALL {
+        for binding in settings.bindings.get(action) {
+            let (is_pressed, is_just_pressed) = match *binding {
+                Binding::Key(key) => (keyboard.pressed(key), keyboard.just_pressed(key)),
+                Binding::GamepadButton(button_type) => gamepads
+                    .iter()
+                    .map(|gamepad| GamepadButton::new(gamepad, button_type))
+                    .fold((false, false), |(pressed, just_pressed), button| {
+                        (
+                            pressed || gamepad_buttons.pressed(button),
+                            just_pressed || gamepad_buttons.just_pressed(button),
+                        )
+                    }),
+            };
+            if is_pressed {
+                pressed.insert(action);
+            }
+            if is_just_pressed {
+                just_pressed.insert(action);
+            }
+        }
+    }
+}
+
+/// Run condition that is true on the frame the action is pressed.
+pub fn action_just_pressed(action: Action) -> impl FnMut(Res<ActionState>) -> bool + Clone {
+    move |action_state: Res<ActionState>| action_state.just_pressed(action)
+}
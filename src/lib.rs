@@ -1,14 +1,19 @@
 #[cfg(feature = "dev")]
 mod dev_tools;
 mod game;
+mod input;
+mod post_process;
 mod screen;
+mod settings;
+mod tween;
 mod ui;
+mod viewport;
 
 use bevy::{
     asset::AssetMetaCheck,
     audio::{AudioPlugin, Volume},
     prelude::*,
-    window::WindowMode,
+    window::{WindowMode, WindowResizeConstraints},
 };
 
 pub struct AppPlugin;
@@ -21,9 +26,6 @@ impl Plugin for AppPlugin {
             (AppSet::TickTimers, AppSet::RecordInput, AppSet::Update).chain(),
         );
 
-        // Spawn the main camera.
-        app.add_systems(Startup, spawn_camera);
-
         // Add Bevy plugins.
         app.add_plugins(
             DefaultPlugins
@@ -41,13 +43,19 @@ impl Plugin for AppPlugin {
                         fit_canvas_to_parent: true,
                         prevent_default_event_handling: true,
                         mode: WindowMode::Windowed,
-                        resolution: (1280., 720.).into(),
-                        resizable: false, // Disable resizing
+                        resolution: viewport::VIEW_SIZE.into(),
+                        resize_constraints: WindowResizeConstraints {
+                            min_width: viewport::CANVAS_SIZE.x as f32,
+                            min_height: viewport::CANVAS_SIZE.y as f32,
+                            ..default()
+                        },
                         ..default()
                     }
                     .into(),
                     ..default()
                 })
+                // Pixel art should stay crisp when scaled.
+                .set(ImagePlugin::default_nearest())
                 .set(AudioPlugin {
                     global_volume: GlobalVolume {
                         volume: Volume::new(0.3),
@@ -57,7 +65,16 @@ impl Plugin for AppPlugin {
         );
 
         // Add other plugins.
-        app.add_plugins((game::plugin, screen::plugin, ui::plugin));
+        app.add_plugins((
+            settings::plugin,
+            input::plugin,
+            viewport::plugin,
+            post_process::plugin,
+            tween::plugin,
+            game::plugin,
+            screen::plugin,
+            ui::plugin,
+        ));
 
         // Enable dev tools for dev builds.
         #[cfg(feature = "dev")]
@@ -77,17 +94,3 @@ enum AppSet {
     /// Do everything else (consider splitting this into further variants).
     Update,
 }
-
-fn spawn_camera(mut commands: Commands) {
-    commands.spawn((
-        Name::new("Camera"),
-        Camera2dBundle::default(),
-        // Render all UI to this camera.
-        // Not strictly necessary since we only use one camera,
-        // but if we don't use this component, our UI will disappear as soon
-        // as we add another camera. This includes indirect ways of adding cameras like using
-        // [ui node outlines](https://bevyengine.org/news/bevy-0-14/#ui-node-outline-gizmos)
-        // for debugging. So it's good to have this here for future-proofing.
-        IsDefaultUiCamera,
-    ));
-}
@@ -0,0 +1,291 @@
+//! Full-screen effects on the rendered world: vignette, film grain, CRT curvature,
+//! bloom and a screen warp pulse for transitions.
+//! They are applied when the canvas is drawn to the window, so UI on top is never affected.
+//! Chapter logic and settings tune them through the [`PostProcess`] resource,
+//! and [`WarpPulse`] ripples the screen once.
+
+use bevy::{
+    prelude::*,
+    render::render_resource::{AsBindGroup, ShaderRef},
+    sprite::{Material2d, Material2dPlugin},
+};
+
+use crate::settings::Settings;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_plugins(Material2dPlugin::<PostProcessMaterial>::default());
+    app.register_type::<PostProcess>();
+    app.init_resource::<PostProcess>();
+    app.init_resource::<ActiveWarp>();
+    app.observe(start_warp_pulse);
+    app.add_systems(
+        Update,
+        (
+            apply_settings.run_if(resource_changed::<Settings>),
+            advance_warp,
+            sync_post_process,
+        )
+            .chain(),
+    );
+}
+
+/// Which effects are on and how strong they are.
+#[derive(Resource, Reflect, Debug, Clone, PartialEq)]
+#[reflect(Resource)]
+pub struct PostProcess {
+    /// Turns every effect off at once. Mirrors [`Settings::post_processing`].
+    pub enabled: bool,
+    pub vignette: Vignette,
+    pub grain: FilmGrain,
+    pub curvature: Curvature,
+    pub bloom: Bloom,
+    /// Lets [`WarpPulse`] distort the screen.
+    pub warp: bool,
+}
+
+impl Default for PostProcess {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            vignette: Vignette::default(),
+            grain: FilmGrain::default(),
+            curvature: Curvature::default(),
+            bloom: Bloom::default(),
+            warp: true,
+        }
+    }
+}
+
+/// Darkens the corners of the screen.
+#[derive(Reflect, Debug, Clone, Copy, PartialEq)]
+pub struct Vignette {
+    pub enabled: bool,
+    /// How dark the corners get, between 0 and 1.
+    pub strength: f32,
+    /// How far from the center the darkening starts, where 1 is the screen's edge.
+    pub radius: f32,
+}
+
+impl Default for Vignette {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            strength: 0.4,
+            radius: 0.6,
+        }
+    }
+}
+
+/// Flickering noise over the whole screen.
+#[derive(Reflect, Debug, Clone, Copy, PartialEq)]
+pub struct FilmGrain {
+    pub enabled: bool,
+    /// How far the noise moves each color, between 0 and 1.
+    pub strength: f32,
+}
+
+impl Default for FilmGrain {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            strength: 0.05,
+        }
+    }
+}
+
+/// Bends the screen like an old CRT.
+#[derive(Reflect, Debug, Clone, Copy, PartialEq)]
+pub struct Curvature {
+    pub enabled: bool,
+    /// How much the edges bend. Small values look best.
+    pub amount: f32,
+}
+
+impl Default for Curvature {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            amount: 0.03,
+        }
+    }
+}
+
+/// Makes bright pixels glow into their surroundings.
+#[derive(Reflect, Debug, Clone, Copy, PartialEq)]
+pub struct Bloom {
+    pub enabled: bool,
+    /// How bright a pixel must be to glow, between 0 and 1.
+    pub threshold: f32,
+    pub intensity: f32,
+}
+
+impl Default for Bloom {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            threshold: 0.7,
+            intensity: 0.6,
+        }
+    }
+}
+
+/// Ripples the screen outward from the center, e.g. when the room changes.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct WarpPulse {
+    /// Peak displacement as a fraction of the screen.
+    pub amount: f32,
+    /// Seconds the ripple lasts.
+    pub duration: f32,
+}
+
+/// The warp pulse currently playing, if any. A new pulse replaces the old one.
+#[derive(Resource, Debug, Default)]
+struct ActiveWarp {
+    pulse: Option<WarpPulse>,
+    elapsed: f32,
+}
+
+impl ActiveWarp {
+    /// How much of the warp is kept with reduced motion on.
+    const REDUCED_MOTION_SCALE: f32 = 0.25;
+
+    /// Rises and falls once over the pulse.
+    fn strength(&self) -> f32 {
+        let Some(pulse) = self.pulse else {
+            return 0.0;
+        };
+        let t = (self.elapsed / pulse.duration).clamp(0.0, 1.0);
+        pulse.amount * (t * std::f32::consts::PI).sin()
+    }
+}
+
+/// Draws the canvas with every effect applied.
+#[derive(Asset, TypePath, AsBindGroup, Debug, Clone)]
+pub struct PostProcessMaterial {
+    #[uniform(0)]
+    vignette_strength: f32,
+    #[uniform(0)]
+    vignette_radius: f32,
+    #[uniform(0)]
+    grain: f32,
+    #[uniform(0)]
+    curvature: f32,
+    #[uniform(0)]
+    bloom_threshold: f32,
+    #[uniform(0)]
+    bloom_intensity: f32,
+    #[uniform(0)]
+    warp: f32,
+    /// WebGL2 needs uniforms to be a multiple of 16 bytes.
+    #[uniform(0)]
+    _webgl2_padding: f32,
+    #[texture(1)]
+    #[sampler(2)]
+    canvas: Handle<Image>,
+}
+
+impl PostProcessMaterial {
+    /// A material that shows `canvas` with no effects until the next sync.
+    pub fn new(canvas: Handle<Image>) -> Self {
+        Self {
+            vignette_strength: 0.0,
+            vignette_radius: 1.0,
+            grain: 0.0,
+            curvature: 0.0,
+            bloom_threshold: 1.0,
+            bloom_intensity: 0.0,
+            warp: 0.0,
+            _webgl2_padding: 0.0,
+            canvas,
+        }
+    }
+
+    /// Copy the effects in `post_process`. Effects that are off end up with no strength.
+    fn set(&mut self, post_process: &PostProcess, warp: f32) {
+        let on = |enabled: bool| post_process.enabled && enabled;
+        let PostProcess {
+            vignette,
+            grain,
+            curvature,
+            bloom,
+            ..
+        } = post_process;
+        self.vignette_strength = if on(vignette.enabled) {
+            vignette.strength
+        } else {
+            0.0
+        };
+        self.vignette_radius = vignette.radius;
+        self.grain = if on(grain.enabled) {
+            grain.strength
+        } else {
+            0.0
+        };
+        self.curvature = if on(curvature.enabled) {
+            curvature.amount
+        } else {
+            0.0
+        };
+        self.bloom_threshold = bloom.threshold;
+        self.bloom_intensity = if on(bloom.enabled) {
+            bloom.intensity
+        } else {
+            0.0
+        };
+        self.warp = if on(post_process.warp) { warp } else { 0.0 };
+    }
+}
+
+impl Material2d for PostProcessMaterial {
+    fn fragment_shader() -> ShaderRef {
+        "shaders/post_process.wgsl".into()
+    }
+}
+
+fn apply_settings(settings: Res<Settings>, mut post_process: ResMut<PostProcess>) {
+    if post_process.enabled != settings.post_processing {
+        post_process.enabled = settings.post_processing;
+    }
+}
+
+fn start_warp_pulse(trigger: Trigger<WarpPulse>, mut warp: ResMut<ActiveWarp>) {
+    let pulse = *trigger.event();
+    if pulse.duration <= 0.0 {
+        return;
+    }
+    warp.pulse = Some(pulse);
+    warp.elapsed = 0.0;
+}
+
+fn advance_warp(time: Res<Time>, mut warp: ResMut<ActiveWarp>) {
+    let Some(pulse) = warp.pulse else {
+        return;
+    };
+    warp.elapsed += time.delta_seconds();
+    if warp.elapsed >= pulse.duration {
+        warp.pulse = None;
+    }
+}
+
+fn sync_post_process(
+    post_process: Res<PostProcess>,
+    warp: Res<ActiveWarp>,
+    settings: Res<Settings>,
+    material_query: Query<&Handle<PostProcessMaterial>>,
+    mut materials: ResMut<Assets<PostProcessMaterial>>,
+) {
+    // The warp is the only effect that animates on the CPU.
+    if !post_process.is_changed() && !warp.is_changed() {
+        return;
+    }
+    let scale = if settings.reduced_motion {
+        ActiveWarp::REDUCED_MOTION_SCALE
+    } else {
+        1.0
+    };
+    for handle in &material_query {
+        if let Some(material) = materials.get_mut(handle) {
+            material.set(&post_process, warp.strength() * scale);
+        }
+    }
+}
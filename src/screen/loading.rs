@@ -5,7 +5,7 @@ use bevy::prelude::*;
 
 use super::Screen;
 use crate::{
-    game::assets::{FontKey, HandleMap, ImageKey, SfxKey, SoundtrackKey},
+    game::assets::{AsepriteKey, FontKey, HandleMap, ImageKey, RoomLayouts, SfxKey, SoundtrackKey},
     ui::prelude::*,
 };
 
@@ -32,11 +32,15 @@ fn all_assets_loaded(
     sfx_handles: Res<HandleMap<SfxKey>>,
     soundtrack_handles: Res<HandleMap<SoundtrackKey>>,
     font_handles: Res<HandleMap<FontKey>>,
+    aseprite_handles: Res<HandleMap<AsepriteKey>>,
+    room_layouts: Res<RoomLayouts>,
 ) -> bool {
     image_handles.all_loaded(&asset_server)
         && sfx_handles.all_loaded(&asset_server)
         && soundtrack_handles.all_loaded(&asset_server)
         && font_handles.all_loaded(&asset_server)
+        && aseprite_handles.all_loaded(&asset_server)
+        && room_layouts.all_loaded(&asset_server)
 }
 
 fn continue_to_title(mut next_screen: ResMut<NextState<Screen>>) {
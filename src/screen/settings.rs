@@ -0,0 +1,248 @@
+//! A settings screen that can be accessed from the title screen.
+//! Every action can be rebound here; changes are saved right away.
+
+use bevy::{prelude::*, ui::Val::*};
+
+use super::Screen;
+use crate::{
+    game::assets::{FontKey, HandleMap},
+    input::{Action, Binding, Bindings},
+    settings::Settings,
+    ui::prelude::*,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(Screen::Settings), enter_settings);
+    app.add_systems(OnExit(Screen::Settings), exit_settings);
+
+    app.register_type::<(SettingsAction, RebindTarget)>();
+    app.init_resource::<RebindTarget>();
+    app.add_systems(
+        Update,
+        (
+            handle_settings_action,
+            capture_binding,
+            update_binding_labels
+                .run_if(resource_changed::<Settings>.or_else(resource_changed::<RebindTarget>)),
+            update_toggle_labels.run_if(resource_changed::<Settings>),
+        )
+            .chain()
+            .run_if(in_state(Screen::Settings)),
+    );
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+enum SettingsAction {
+    Rebind(Action),
+    Toggle(Toggle),
+    Reset,
+    Back,
+}
+
+/// The action waiting for its next key press, if any.
+#[derive(Resource, Reflect, Default, Debug)]
+#[reflect(Resource)]
+struct RebindTarget(Option<Action>);
+
+/// Marks the label that lists an action's bindings.
+#[derive(Component, Debug, Clone, Copy)]
+struct BindingLabel(Action);
+
+/// An on/off setting, also marking the button that flips it.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+enum Toggle {
+    Joystick,
+    ReducedMotion,
+    Fullscreen,
+    PostProcessing,
+}
+
+impl Toggle {
+    fn value(self, settings: &mut Settings) -> &mut bool {
+        match self {
+            Toggle::Joystick => &mut settings.virtual_joystick,
+            Toggle::ReducedMotion => &mut settings.reduced_motion,
+            Toggle::Fullscreen => &mut settings.fullscreen,
+            Toggle::PostProcessing => &mut settings.post_processing,
+        }
+    }
+
+    fn label(self, settings: &Settings) -> String {
+        let (name, value) = match self {
+            Toggle::Joystick => ("Joystick", settings.virtual_joystick),
+            Toggle::ReducedMotion => ("Reduced Motion", settings.reduced_motion),
+            Toggle::Fullscreen => ("Fullscreen", settings.fullscreen),
+            Toggle::PostProcessing => ("Screen Effects", settings.post_processing),
+        };
+        let state = if value { "On" } else { "Off" };
+        format!("{name}: {state}")
+    }
+}
+
+fn enter_settings(
+    mut commands: Commands,
+    font_handles: Res<HandleMap<FontKey>>,
+    settings: Res<Settings>,
+) {
+    commands
+        .ui_root()
+        .insert(StateScoped(Screen::Settings))
+        .with_children(|children| {
+            children.label(
+                "Pick an action, then press a key or button to add or remove it.",
+                &font_handles,
+            );
+            for action in Action::ALL {
+                children
+                    .spawn((Name::new("Binding Row"), row()))
+                    .with_children(|children| {
+                        children
+                            .button(action.label(), &font_handles)
+                            .insert((SettingsAction::Rebind(action), wide_button()));
+                        children
+                            .label(settings.bindings.label(action), &font_handles)
+                            .insert(BindingLabel(action));
+                    });
+            }
+            children
+                .spawn((Name::new("Toggle Buttons"), row()))
+                .with_children(|children| {
+                    for toggle in [
+                        Toggle::Joystick,
+                        Toggle::ReducedMotion,
+                        Toggle::Fullscreen,
+                        Toggle::PostProcessing,
+                    ] {
+                        children
+                            .button(toggle.label(&settings), &font_handles)
+                            .insert((SettingsAction::Toggle(toggle), toggle, wide_button()));
+                    }
+                });
+            children
+                .spawn((Name::new("Settings Buttons"), row()))
+                .with_children(|children| {
+                    children
+                        .button("Reset", &font_handles)
+                        .insert(SettingsAction::Reset);
+                    children
+                        .button("Back", &font_handles)
+                        .insert(SettingsAction::Back);
+                });
+        });
+}
+
+fn row() -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            align_items: AlignItems::Center,
+            column_gap: Px(10.0),
+            row_gap: Px(10.0),
+            // Wrap rather than run off a narrow screen.
+            flex_wrap: FlexWrap::Wrap,
+            justify_content: JustifyContent::End,
+            ..default()
+        },
+        ..default()
+    }
+}
+
+/// Buttons here hold longer text than the default widget fits.
+fn wide_button() -> Style {
+    Style {
+        width: Px(320.0),
+        height: Px(65.0),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    }
+}
+
+fn exit_settings(mut target: ResMut<RebindTarget>) {
+    target.0 = None;
+}
+
+fn handle_settings_action(
+    mut next_screen: ResMut<NextState<Screen>>,
+    mut button_query: InteractionQuery<&SettingsAction>,
+    mut settings: ResMut<Settings>,
+    mut target: ResMut<RebindTarget>,
+) {
+    for (interaction, action) in &mut button_query {
+        if matches!(interaction, Interaction::Pressed) {
+            match action {
+                SettingsAction::Rebind(action) => target.0 = Some(*action),
+                SettingsAction::Toggle(toggle) => {
+                    let value = toggle.value(&mut settings);
+                    *value = !*value;
+                }
+                SettingsAction::Reset => {
+                    settings.bindings = Bindings::default();
+                    target.0 = None;
+                }
+                SettingsAction::Back => next_screen.set(Screen::Title),
+            }
+        }
+    }
+}
+
+fn capture_binding(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    mut settings: ResMut<Settings>,
+    mut target: ResMut<RebindTarget>,
+) {
+    let Some(action) = target.0 else {
+        return;
+    };
+    // The press that opened the rebind isn't the binding.
+    if target.is_changed() {
+        return;
+    }
+    let binding = keyboard
+        .get_just_pressed()
+        .next()
+        .map(|&key| Binding::Key(key))
+        .or_else(|| {
+            gamepad_buttons
+                .get_just_pressed()
+                .next()
+                .map(|button| Binding::GamepadButton(button.button_type))
+        });
+    if let Some(binding) = binding {
+        settings.bindings.toggle(action, binding);
+        target.0 = None;
+    }
+}
+
+fn update_binding_labels(
+    settings: Res<Settings>,
+    target: Res<RebindTarget>,
+    label_query: Query<(&BindingLabel, &Children)>,
+    mut text_query: Query<&mut Text>,
+) {
+    for (label, children) in &label_query {
+        let value = if target.0 == Some(label.0) {
+            "Press a key or button...".to_string()
+        } else {
+            settings.bindings.label(label.0)
+        };
+        let mut texts = text_query.iter_many_mut(children);
+        while let Some(mut text) = texts.fetch_next() {
+            text.sections[0].value.clone_from(&value);
+        }
+    }
+}
+
+fn update_toggle_labels(
+    settings: Res<Settings>,
+    toggle_query: Query<(&Toggle, &Children)>,
+    mut text_query: Query<&mut Text>,
+) {
+    for (toggle, children) in &toggle_query {
+        let mut texts = text_query.iter_many_mut(children);
+        while let Some(mut text) = texts.fetch_next() {
+            text.sections[0].value = toggle.label(&settings);
+        }
+    }
+}
@@ -1,28 +1,25 @@
 //! A splash screen that plays briefly at startup.
 
+use std::time::Duration;
+
 use bevy::{
     prelude::*,
     render::texture::{ImageLoaderSettings, ImageSampler},
 };
 
 use super::Screen;
-use crate::{ui::prelude::*, AppSet};
+use crate::{
+    input::{action_just_pressed, Action},
+    tween::{Ease, Tween, TweenTarget},
+    ui::prelude::*,
+    AppSet,
+};
 
 pub(super) fn plugin(app: &mut App) {
     // Spawn splash screen.
     app.insert_resource(ClearColor(SPLASH_BACKGROUND_COLOR));
     app.add_systems(OnEnter(Screen::Splash), spawn_splash);
 
-    // Animate splash screen.
-    app.add_systems(
-        Update,
-        (
-            tick_fade_in_out.in_set(AppSet::TickTimers),
-            apply_fade_in_out.in_set(AppSet::Update),
-        )
-            .run_if(in_state(Screen::Splash)),
-    );
-
     // Add splash timer.
     app.register_type::<SplashTimer>();
     app.add_systems(OnEnter(Screen::Splash), insert_splash_timer);
@@ -35,6 +32,12 @@ pub(super) fn plugin(app: &mut App) {
         )
             .run_if(in_state(Screen::Splash)),
     );
+    app.add_systems(
+        Update,
+        skip_splash
+            .in_set(AppSet::Update)
+            .run_if(in_state(Screen::Splash).and_then(action_just_pressed(Action::Skip))),
+    );
 }
 
 const SPLASH_BACKGROUND_COLOR: Color = Color::srgb(0.1960, 0.2, 0.3254);
@@ -70,47 +73,32 @@ fn spawn_splash(mut commands: Commands, asset_server: Res<AssetServer>) {
                     )),
                     ..default()
                 },
-                UiImageFadeInOut {
-                    total_duration: SPLASH_DURATION_SECS,
-                    fade_duration: SPLASH_FADE_DURATION_SECS,
-                    t: 0.0,
-                },
+                fade_in_out(),
             ));
         });
 }
 
-#[derive(Component, Reflect)]
-#[reflect(Component)]
-struct UiImageFadeInOut {
-    /// Total duration in seconds.
-    total_duration: f32,
-    /// Fade duration in seconds.
-    fade_duration: f32,
-    /// Current progress in seconds, between 0 and [`Self::total_duration`].
-    t: f32,
-}
-
-impl UiImageFadeInOut {
-    fn alpha(&self) -> f32 {
-        // Normalize by duration.
-        let t = (self.t / self.total_duration).clamp(0.0, 1.0);
-        let fade = self.fade_duration / self.total_duration;
-
-        // Regular trapezoid-shaped graph, flat at the top with alpha = 1.0.
-        ((1.0 - (2.0 * t - 1.0).abs()) / fade).min(1.0)
-    }
-}
-
-fn tick_fade_in_out(time: Res<Time>, mut animation_query: Query<&mut UiImageFadeInOut>) {
-    for mut anim in &mut animation_query {
-        anim.t += time.delta_seconds();
-    }
-}
-
-fn apply_fade_in_out(mut animation_query: Query<(&UiImageFadeInOut, &mut UiImage)>) {
-    for (anim, mut image) in &mut animation_query {
-        image.color.set_alpha(anim.alpha())
-    }
+/// Fade in, hold, and fade back out over the length of the splash.
+fn fade_in_out() -> Tween {
+    let fade = Duration::from_secs_f32(SPLASH_FADE_DURATION_SECS);
+    let hold = Duration::from_secs_f32(SPLASH_DURATION_SECS - 2.0 * SPLASH_FADE_DURATION_SECS);
+    Tween::new(
+        fade,
+        Ease::Linear,
+        TweenTarget::ImageAlpha {
+            start: 0.0,
+            end: 1.0,
+        },
+    )
+    .then(hold, Ease::Linear, TweenTarget::Wait)
+    .then(
+        fade,
+        Ease::Linear,
+        TweenTarget::ImageAlpha {
+            start: 1.0,
+            end: 0.0,
+        },
+    )
 }
 
 #[derive(Resource, Debug, Clone, PartialEq, Reflect)]
@@ -140,3 +128,7 @@ fn check_splash_timer(timer: ResMut<SplashTimer>, mut next_screen: ResMut<NextSt
         next_screen.set(Screen::Loading);
     }
 }
+
+fn skip_splash(mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::Loading);
+}
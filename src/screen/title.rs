@@ -7,8 +7,8 @@ use super::Screen;
 use crate::ui::prelude::*;
 
 use crate::game::{
-    animation::BasicAnimation,
-    assets::{FontKey, HandleMap, ImageKey},
+    animation::AnimationController,
+    assets::{AsepriteKey, FontKey, HandleMap, ImageKey},
 };
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(OnEnter(Screen::Title), enter_title);
@@ -21,6 +21,7 @@ pub(super) fn plugin(app: &mut App) {
 #[reflect(Component)]
 enum TitleAction {
     Play,
+    Settings,
     Credits,
     /// Exit doesn't work well with embedded applications.
     #[cfg(not(target_family = "wasm"))]
@@ -36,6 +37,9 @@ fn enter_title(mut commands: Commands, font_handles: Res<HandleMap<FontKey>>) {
             children
                 .button("Play", &font_handles)
                 .insert(TitleAction::Play);
+            children
+                .button("Settings", &font_handles)
+                .insert(TitleAction::Settings);
             children
                 .button("Credits", &font_handles)
                 .insert(TitleAction::Credits);
@@ -56,6 +60,7 @@ fn handle_title_action(
         if matches!(interaction, Interaction::Pressed) {
             match action {
                 TitleAction::Play => next_screen.set(Screen::Playing),
+                TitleAction::Settings => next_screen.set(Screen::Settings),
                 TitleAction::Credits => next_screen.set(Screen::Credits),
 
                 #[cfg(not(target_family = "wasm"))]
@@ -76,14 +81,9 @@ fn make_title_animation(
     _trigger: Trigger<MakeTitleAnimation>,
     mut commands: Commands,
     image_handles: Res<HandleMap<ImageKey>>,
-    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    aseprite_handles: Res<HandleMap<AsepriteKey>>,
 ) {
-    // Create a texture atlas
-    let layout =
-        TextureAtlasLayout::from_grid(UVec2 { x: 480, y: 270 }, 2, 1, Some(UVec2::splat(1)), None);
-    let texture_atlas_layout = texture_atlas_layouts.add(layout);
     let translation = Vec3::new(0.0, 0.0, 0.0);
-    let title_animation = BasicAnimation::new(2);
 
     commands.spawn((
         Name::new("Title Animation"),
@@ -94,11 +94,10 @@ fn make_title_animation(
                 .with_translation(translation),
             ..Default::default()
         },
-        TextureAtlas {
-            layout: texture_atlas_layout.clone(),
-            index: title_animation.get_atlas_index(),
-        },
+        AnimationController::new(
+            aseprite_handles[&AsepriteKey::TitleImage].clone_weak(),
+            "idle",
+        ),
         StateScoped(Screen::Title),
-        title_animation,
     ));
 }
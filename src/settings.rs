@@ -0,0 +1,80 @@
+//! Player settings, persisted to `settings.ron` in the working directory.
+//! Web builds have no file system, so they keep settings for the session only.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::input::Bindings;
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<Settings>();
+    app.insert_resource(Settings::load());
+    app.add_systems(Update, save_settings.run_if(resource_changed::<Settings>));
+}
+
+#[cfg(not(target_family = "wasm"))]
+const SETTINGS_PATH: &str = "settings.ron";
+
+#[derive(Resource, Reflect, Serialize, Deserialize, Debug, Clone)]
+#[reflect(Resource)]
+#[serde(default)]
+pub struct Settings {
+    pub bindings: Bindings,
+    /// Show an on-screen joystick once touch input is detected.
+    pub virtual_joystick: bool,
+    /// Tone down camera shake, zoom and sway.
+    pub reduced_motion: bool,
+    /// Fill the screen with a borderless window instead of a regular one.
+    pub fullscreen: bool,
+    /// Vignette, grain, curvature, bloom and warp on the world.
+    pub post_processing: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            bindings: Bindings::default(),
+            virtual_joystick: true,
+            reduced_motion: false,
+            fullscreen: false,
+            post_processing: true,
+        }
+    }
+}
+
+impl Settings {
+    #[cfg(not(target_family = "wasm"))]
+    fn load() -> Self {
+        let Ok(contents) = std::fs::read_to_string(SETTINGS_PATH) else {
+            return Self::default();
+        };
+        ron::from_str(&contents).unwrap_or_else(|error| {
+            warn!("Ignoring invalid {SETTINGS_PATH}: {error}");
+            Self::default()
+        })
+    }
+
+    #[cfg(target_family = "wasm")]
+    fn load() -> Self {
+        Self::default()
+    }
+}
+
+fn save_settings(settings: Res<Settings>) {
+    // The resource counts as changed when it is first inserted.
+    if settings.is_added() {
+        return;
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    {
+        let result = ron::ser::to_string_pretty(&*settings, default())
+            .map_err(|error| error.to_string())
+            .and_then(|contents| {
+                std::fs::write(SETTINGS_PATH, contents).map_err(|error| error.to_string())
+            });
+        if let Err(error) = result {
+            warn!("Failed to save {SETTINGS_PATH}: {error}");
+        }
+    }
+}
@@ -0,0 +1,285 @@
+//! Ease values on an entity from one setting to another over time.
+//! A [`Tween`] is a sequence of steps. Each step animates one or more [`TweenTarget`]s at once
+//! with an [`Ease`] curve, and the whole sequence can repeat.
+//! When it is done, [`TweenCompleted`] is triggered on the entity and the tween is removed.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::AppSet;
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<Tween>();
+    app.add_systems(Update, update_tweens.in_set(AppSet::Update));
+}
+
+/// Easing curves, mapping progress between 0 and 1 to eased progress.
+#[derive(Reflect, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Ease {
+    #[default]
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicOut,
+    SineInOut,
+    /// Overshoots a little before settling, for things popping into place.
+    BackOut,
+}
+
+impl Ease {
+    pub fn apply(self, t: f32) -> f32 {
+        use std::f32::consts::PI;
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Ease::Linear => t,
+            Ease::QuadIn => t * t,
+            Ease::QuadOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Ease::QuadInOut => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+                }
+            }
+            Ease::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Ease::SineInOut => -((PI * t).cos() - 1.0) / 2.0,
+            Ease::BackOut => {
+                const C1: f32 = 1.70158;
+                const C3: f32 = C1 + 1.0;
+                1.0 + C3 * (t - 1.0).powi(3) + C1 * (t - 1.0).powi(2)
+            }
+        }
+    }
+}
+
+/// A property a tween animates, with the values it goes between.
+#[derive(Reflect, Debug, Clone, Copy, PartialEq)]
+pub enum TweenTarget {
+    Translation {
+        start: Vec3,
+        end: Vec3,
+    },
+    Scale {
+        start: Vec3,
+        end: Vec3,
+    },
+    /// Rotation around the z axis, in radians.
+    Rotation {
+        start: f32,
+        end: f32,
+    },
+    SpriteColor {
+        start: Color,
+        end: Color,
+    },
+    BackgroundColor {
+        start: Color,
+        end: Color,
+    },
+    /// The alpha of every section of a [`Text`].
+    TextAlpha {
+        start: f32,
+        end: f32,
+    },
+    /// The alpha of a [`UiImage`].
+    ImageAlpha {
+        start: f32,
+        end: f32,
+    },
+    /// Nothing, for pauses in a sequence.
+    Wait,
+}
+
+#[derive(Reflect, Debug, Clone)]
+struct TweenStep {
+    duration: Duration,
+    ease: Ease,
+    targets: Vec<TweenTarget>,
+}
+
+/// How many times a tween plays its sequence.
+#[derive(Reflect, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Repeat {
+    #[default]
+    Once,
+    Times(u32),
+    Forever,
+}
+
+/// A sequence of eased steps.
+#[derive(Component, Reflect, Debug, Clone)]
+#[reflect(Component)]
+pub struct Tween {
+    steps: Vec<TweenStep>,
+    repeat: Repeat,
+    /// Index of the current step.
+    index: usize,
+    elapsed: Duration,
+    plays: u32,
+}
+
+impl Tween {
+    /// A tween with a single step.
+    pub fn new(duration: Duration, ease: Ease, target: TweenTarget) -> Self {
+        Self {
+            steps: Vec::new(),
+            repeat: Repeat::Once,
+            index: 0,
+            elapsed: Duration::ZERO,
+            plays: 0,
+        }
+        .then(duration, ease, target)
+    }
+
+    /// Add a step that starts when the previous one is done.
+    pub fn then(mut self, duration: Duration, ease: Ease, target: TweenTarget) -> Self {
+        self.steps.push(TweenStep {
+            duration,
+            ease,
+            targets: vec![target],
+        });
+        self
+    }
+
+    /// Animate another property during the last step.
+    pub fn with(mut self, target: TweenTarget) -> Self {
+        if let Some(step) = self.steps.last_mut() {
+            step.targets.push(target);
+        }
+        self
+    }
+
+    pub fn with_repeat(mut self, repeat: Repeat) -> Self {
+        self.repeat = repeat;
+        self
+    }
+
+    /// The background color the tween ends up at, if it animates one.
+    pub fn background_end(&self) -> Option<Color> {
+        self.steps
+            .iter()
+            .flat_map(|step| &step.targets)
+            .rev()
+            .find_map(|target| match target {
+                TweenTarget::BackgroundColor { end, .. } => Some(*end),
+                _ => None,
+            })
+    }
+
+    /// Advance by `delta`, returning the index of the step to show, its eased progress,
+    /// and whether the tween is done.
+    fn advance(&mut self, delta: Duration) -> (usize, f32, bool) {
+        self.elapsed += delta;
+        loop {
+            let Some(step) = self.steps.get(self.index) else {
+                return (self.index, 1.0, true);
+            };
+            if self.elapsed < step.duration {
+                let t = self.elapsed.as_secs_f32() / step.duration.as_secs_f32();
+                return (self.index, step.ease.apply(t), false);
+            }
+            if self.index + 1 < self.steps.len() {
+                self.elapsed -= step.duration;
+                self.index += 1;
+                continue;
+            }
+
+            // The last step just finished.
+            self.plays += 1;
+            let again = match self.repeat {
+                Repeat::Once => false,
+                Repeat::Times(times) => self.plays < times,
+                Repeat::Forever => true,
+            };
+            // Without any duration it would repeat forever within one frame.
+            if !again || self.steps.iter().all(|step| step.duration.is_zero()) {
+                return (self.index, 1.0, true);
+            }
+            self.elapsed -= step.duration;
+            self.index = 0;
+        }
+    }
+}
+
+/// Triggered on an entity when its [`Tween`] is done.
+#[derive(Event, Debug)]
+pub struct TweenCompleted;
+
+fn update_tweens(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut tween_query: Query<(
+        Entity,
+        &mut Tween,
+        Option<&mut Transform>,
+        Option<&mut Sprite>,
+        Option<&mut BackgroundColor>,
+        Option<&mut Text>,
+        Option<&mut UiImage>,
+    )>,
+) {
+    for (entity, mut tween, mut transform, mut sprite, mut background, mut text, mut image) in
+        &mut tween_query
+    {
+        let (index, t, done) = tween.advance(time.delta());
+        for target in tween
+            .steps
+            .get(index)
+            .into_iter()
+            .flat_map(|step| &step.targets)
+        {
+            match *target {
+                TweenTarget::Translation { start, end } => {
+                    if let Some(transform) = transform.as_mut() {
+                        transform.translation = start.lerp(end, t);
+                    }
+                }
+                TweenTarget::Scale { start, end } => {
+                    if let Some(transform) = transform.as_mut() {
+                        transform.scale = start.lerp(end, t);
+                    }
+                }
+                TweenTarget::Rotation { start, end } => {
+                    if let Some(transform) = transform.as_mut() {
+                        transform.rotation = Quat::from_rotation_z(start.lerp(end, t));
+                    }
+                }
+                TweenTarget::SpriteColor { start, end } => {
+                    if let Some(sprite) = sprite.as_mut() {
+                        sprite.color = mix(start, end, t);
+                    }
+                }
+                TweenTarget::BackgroundColor { start, end } => {
+                    if let Some(background) = background.as_mut() {
+                        background.0 = mix(start, end, t);
+                    }
+                }
+                TweenTarget::TextAlpha { start, end } => {
+                    if let Some(text) = text.as_mut() {
+                        for section in &mut text.sections {
+                            section.style.color.set_alpha(start.lerp(end, t));
+                        }
+                    }
+                }
+                TweenTarget::ImageAlpha { start, end } => {
+                    if let Some(image) = image.as_mut() {
+                        image.color.set_alpha(start.lerp(end, t));
+                    }
+                }
+                TweenTarget::Wait => {}
+            }
+        }
+
+        if done {
+            commands.entity(entity).remove::<Tween>();
+            commands.trigger_targets(TweenCompleted, entity);
+        }
+    }
+}
+
+/// Blend colors in linear space, which looks even across the blend.
+fn mix(start: Color, end: Color, t: f32) -> Color {
+    LinearRgba::from(start).mix(&end.into(), t).into()
+}
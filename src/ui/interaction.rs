@@ -1,6 +1,12 @@
+use std::time::Duration;
+
 use bevy::prelude::*;
 
-use crate::game::{assets::SfxKey, audio::sfx::PlaySfx};
+use super::navigation::FocusedButton;
+use crate::{
+    game::{assets::SfxKey, audio::sfx::PlaySfx},
+    tween::{Ease, Tween, TweenTarget},
+};
 
 pub(super) fn plugin(app: &mut App) {
     app.register_type::<InteractionPalette>();
@@ -19,16 +25,41 @@ pub struct InteractionPalette {
     pub pressed: Color,
 }
 
+/// How long buttons take to change color.
+const PALETTE_FADE: Duration = Duration::from_millis(100);
+
 fn apply_interaction_palette(
-    mut palette_query: InteractionQuery<(&InteractionPalette, &mut BackgroundColor)>,
+    mut commands: Commands,
+    focused: Res<FocusedButton>,
+    palette_query: Query<(
+        Entity,
+        &Interaction,
+        &InteractionPalette,
+        &BackgroundColor,
+        Option<&Tween>,
+    )>,
 ) {
-    for (interaction, (palette, mut background)) in &mut palette_query {
-        *background = match interaction {
-            Interaction::None => palette.none,
-            Interaction::Hovered => palette.hovered,
+    for (entity, interaction, palette, background, tween) in &palette_query {
+        // The button focused by menu navigation looks hovered.
+        let color = match interaction {
             Interaction::Pressed => palette.pressed,
+            Interaction::Hovered => palette.hovered,
+            Interaction::None if focused.0 == Some(entity) => palette.hovered,
+            Interaction::None => palette.none,
+        };
+        let goal = tween
+            .and_then(Tween::background_end)
+            .unwrap_or(background.0);
+        if goal != color {
+            commands.entity(entity).insert(Tween::new(
+                PALETTE_FADE,
+                Ease::QuadOut,
+                TweenTarget::BackgroundColor {
+                    start: background.0,
+                    end: color,
+                },
+            ));
         }
-        .into();
     }
 }
 
@@ -0,0 +1,110 @@
+//! Move between buttons with the d-pad or arrow keys, so menus work without a mouse.
+//! The focused button is highlighted like a hovered one, and pressing
+//! [`Action::Interact`] or [`Action::Skip`] presses it for one frame,
+//! which [`InteractionQuery`](super::interaction::InteractionQuery) picks up like a click.
+
+use bevy::{prelude::*, ui::UiSystem};
+
+use crate::{
+    game::{assets::SfxKey, audio::sfx::PlaySfx},
+    input::{Action, ActionState},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<FocusedButton>();
+    app.init_resource::<FocusedButton>();
+    app.add_systems(
+        PreUpdate,
+        (move_button_focus, press_focused_button)
+            .chain()
+            .after(UiSystem::Focus),
+    );
+}
+
+/// The button that menu navigation currently points at.
+#[derive(Resource, Reflect, Default, Debug)]
+#[reflect(Resource)]
+pub struct FocusedButton(pub Option<Entity>);
+
+fn move_button_focus(
+    actions: Res<ActionState>,
+    mut focused: ResMut<FocusedButton>,
+    button_query: Query<
+        (Entity, &GlobalTransform, &ViewVisibility, Ref<Interaction>),
+        With<Button>,
+    >,
+    mut commands: Commands,
+) {
+    // Hovering a button with the mouse hands focus back to it.
+    if button_query
+        .iter()
+        .any(|(.., interaction)| interaction.is_changed() && *interaction == Interaction::Hovered)
+    {
+        focused.0 = None;
+        return;
+    }
+
+    let step: isize = if actions.just_pressed(Action::MoveUp)
+        || actions.just_pressed(Action::MoveLeft)
+    {
+        -1
+    } else if actions.just_pressed(Action::MoveDown) || actions.just_pressed(Action::MoveRight) {
+        1
+    } else {
+        // Drop focus when its button goes away, e.g. on a screen change.
+        if focused
+            .0
+            .is_some_and(|entity| !button_query.contains(entity))
+        {
+            focused.0 = None;
+        }
+        return;
+    };
+
+    // Buttons in reading order: top to bottom, then left to right.
+    let mut buttons: Vec<_> = button_query
+        .iter()
+        .filter(|(_, _, visibility, _)| visibility.get())
+        .map(|(entity, transform, ..)| (entity, transform.translation().xy()))
+        .collect();
+    if buttons.is_empty() {
+        return;
+    }
+    buttons.sort_by(|(_, a), (_, b)| a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x)));
+
+    let next = match focused
+        .0
+        .and_then(|entity| buttons.iter().position(|(button, _)| *button == entity))
+    {
+        Some(index) => (index as isize + step).rem_euclid(buttons.len() as isize) as usize,
+        None => 0,
+    };
+    focused.0 = Some(buttons[next].0);
+    commands.trigger(PlaySfx::Key(SfxKey::ButtonHover));
+}
+
+fn press_focused_button(
+    actions: Res<ActionState>,
+    focused: Res<FocusedButton>,
+    mut pressed: Local<Option<Entity>>,
+    mut interaction_query: Query<&mut Interaction>,
+) {
+    // Undo the press from the previous frame, since the UI focus system
+    // only releases buttons when the mouse is released.
+    if let Some(entity) = pressed.take() {
+        if let Ok(mut interaction) = interaction_query.get_mut(entity) {
+            interaction.set_if_neq(Interaction::None);
+        }
+    }
+
+    if !(actions.just_pressed(Action::Interact) || actions.just_pressed(Action::Skip)) {
+        return;
+    }
+    let Some(entity) = focused.0 else {
+        return;
+    };
+    if let Ok(mut interaction) = interaction_query.get_mut(entity) {
+        *interaction = Interaction::Pressed;
+        *pressed = Some(entity);
+    }
+}
@@ -45,6 +45,9 @@ pub trait Widgets {
         text: impl Into<String>,
         font_handles: &Res<HandleMap<FontKey>>,
     ) -> EntityCommands;
+
+    /// Spawn a round on-screen joystick with a [`JoystickKnob`] centered inside it.
+    fn joystick(&mut self, size: f32) -> EntityCommands;
 }
 
 impl<T: Spawn> Widgets for T {
@@ -191,8 +194,49 @@ impl<T: Spawn> Widgets for T {
         });
         entity
     }
+
+    fn joystick(&mut self, size: f32) -> EntityCommands {
+        let knob_size = size * 0.4;
+        let mut entity = self.spawn((
+            Name::new("Joystick"),
+            NodeBundle {
+                style: Style {
+                    width: Px(size),
+                    height: Px(size),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: BackgroundColor(NODE_BACKGROUND.with_alpha(0.4)),
+                border_radius: BorderRadius::MAX,
+                ..default()
+            },
+        ));
+        entity.with_children(|children| {
+            children.spawn((
+                Name::new("Joystick Knob"),
+                JoystickKnob,
+                NodeBundle {
+                    style: Style {
+                        width: Px(knob_size),
+                        height: Px(knob_size),
+                        ..default()
+                    },
+                    background_color: BackgroundColor(BUTTON_TEXT.with_alpha(0.7)),
+                    border_radius: BorderRadius::MAX,
+                    ..default()
+                },
+            ));
+        });
+        entity
+    }
 }
 
+/// Marks the draggable knob of a [`Widgets::joystick`].
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
+#[reflect(Component)]
+pub struct JoystickKnob;
+
 /// An extension trait for spawning UI containers.
 pub trait Containers {
     /// Spawns a root node that covers the full screen
@@ -0,0 +1,246 @@
+//! Render the world at a fixed low resolution and upscale it to the window by whole pixels.
+//! The world camera draws into a [`CANVAS_SIZE`] image, which a second camera shows
+//! in the largest integer multiple that fits the window, with black bars around it.
+//! UI is laid out in the same letterboxed area and scaled so it always measures [`VIEW_SIZE`],
+//! so resizing the window or going fullscreen never changes gameplay or layout.
+//! The canvas is drawn with [`PostProcessMaterial`], so effects never touch the UI.
+
+use bevy::{
+    prelude::*,
+    render::{
+        camera::{RenderTarget, ScalingMode, Viewport},
+        render_resource::{
+            Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+        },
+        texture::ImageSampler,
+        view::RenderLayers,
+    },
+    sprite::MaterialMesh2dBundle,
+    ui::UiSystem,
+    window::{PrimaryWindow, WindowMode},
+};
+
+use crate::{
+    game::camera::{CameraController, CameraEffects},
+    post_process::PostProcessMaterial,
+    settings::Settings,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<Letterbox>();
+    app.init_resource::<Letterbox>();
+    app.insert_resource(Msaa::Off);
+    app.add_systems(Startup, spawn_cameras);
+    // Fit before UI focus so clicks are mapped with this frame's letterbox.
+    app.add_systems(PreUpdate, fit_canvas.before(UiSystem::Focus));
+    app.add_systems(
+        Update,
+        apply_window_mode.run_if(resource_changed::<Settings>),
+    );
+}
+
+/// How much of the world is visible, in world units. UI is laid out in this size too.
+pub const VIEW_SIZE: Vec2 = Vec2::new(1280.0, 720.0);
+
+/// The resolution the world is rendered at.
+pub const CANVAS_SIZE: UVec2 = UVec2::new(320, 180);
+
+/// World units per canvas pixel. Sprites are drawn at 4x, so this is one texel per pixel.
+pub const PIXEL_SIZE: f32 = 4.0;
+
+/// Marks the camera that renders the world into the canvas.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct WorldCamera;
+
+/// Marks the camera that shows the canvas and the UI in the window.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct DisplayCamera;
+
+/// The layer only the canvas sprite and [`DisplayCamera`] are on.
+const DISPLAY_LAYER: usize = 1;
+
+/// Where the canvas sits in the window.
+#[derive(Resource, Reflect, Debug, Clone, Copy, PartialEq)]
+#[reflect(Resource)]
+pub struct Letterbox {
+    /// Window pixels per canvas pixel.
+    pub scale: u32,
+    /// Top-left corner of the canvas in logical window coordinates.
+    pub origin: Vec2,
+    scale_factor: f32,
+}
+
+impl Default for Letterbox {
+    fn default() -> Self {
+        Self {
+            scale: 1,
+            origin: Vec2::ZERO,
+            scale_factor: 1.0,
+        }
+    }
+}
+
+impl Letterbox {
+    /// Convert a logical window position, e.g. the cursor, to canvas pixels.
+    /// This is what [`WorldCamera`] expects for [`Camera::viewport_to_world_2d`].
+    pub fn window_to_canvas(&self, position: Vec2) -> Vec2 {
+        (position - self.origin) * self.scale_factor / self.scale as f32
+    }
+
+    /// Convert a logical window position to UI coordinates.
+    pub fn window_to_ui(&self, position: Vec2) -> Vec2 {
+        self.window_to_canvas(position) * PIXEL_SIZE
+    }
+
+    /// Convert canvas pixels, e.g. from [`Camera::world_to_viewport`], to UI coordinates.
+    pub fn canvas_to_ui(position: Vec2) -> Vec2 {
+        position * PIXEL_SIZE
+    }
+}
+
+fn spawn_cameras(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<PostProcessMaterial>>,
+) {
+    let size = Extent3d {
+        width: CANVAS_SIZE.x,
+        height: CANVAS_SIZE.y,
+        ..default()
+    };
+    let mut canvas = Image {
+        texture_descriptor: TextureDescriptor {
+            label: Some("canvas"),
+            size,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Bgra8UnormSrgb,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        },
+        // Keep pixels sharp when upscaling.
+        sampler: ImageSampler::nearest(),
+        ..default()
+    };
+    canvas.resize(size);
+    let canvas = images.add(canvas);
+
+    let mut world_camera = Camera2dBundle {
+        camera: Camera {
+            // Render before the display camera shows the result.
+            order: -1,
+            target: RenderTarget::Image(canvas.clone()),
+            ..default()
+        },
+        ..default()
+    };
+    world_camera.projection.scale = PIXEL_SIZE;
+    commands.spawn((
+        Name::new("Camera"),
+        world_camera,
+        WorldCamera,
+        CameraController::default(),
+        CameraEffects::default(),
+    ));
+
+    let mut display_camera = Camera2dBundle {
+        camera: Camera {
+            // Paint the letterbox bars.
+            clear_color: ClearColorConfig::Custom(Color::BLACK),
+            ..default()
+        },
+        ..default()
+    };
+    display_camera.projection.scaling_mode = ScalingMode::Fixed {
+        width: CANVAS_SIZE.x as f32,
+        height: CANVAS_SIZE.y as f32,
+    };
+    commands.spawn((
+        Name::new("Display Camera"),
+        display_camera,
+        DisplayCamera,
+        RenderLayers::layer(DISPLAY_LAYER),
+        // Render all UI to this camera.
+        // Not strictly necessary since we only use one camera,
+        // but if we don't use this component, our UI will disappear as soon
+        // as we add another camera. This includes indirect ways of adding cameras like using
+        // [ui node outlines](https://bevyengine.org/news/bevy-0-14/#ui-node-outline-gizmos)
+        // for debugging. So it's good to have this here for future-proofing.
+        IsDefaultUiCamera,
+    ));
+    commands.spawn((
+        Name::new("Canvas"),
+        MaterialMesh2dBundle {
+            mesh: meshes
+                .add(Rectangle::from_size(CANVAS_SIZE.as_vec2()))
+                .into(),
+            material: materials.add(PostProcessMaterial::new(canvas)),
+            ..default()
+        },
+        RenderLayers::layer(DISPLAY_LAYER),
+    ));
+}
+
+fn fit_canvas(
+    window_query: Query<&Window, (With<PrimaryWindow>, Changed<Window>)>,
+    mut camera_query: Query<&mut Camera, With<DisplayCamera>>,
+    mut letterbox: ResMut<Letterbox>,
+    mut ui_scale: ResMut<UiScale>,
+) {
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
+    let window_size = UVec2::new(
+        window.resolution.physical_width(),
+        window.resolution.physical_height(),
+    );
+    // Nothing to fit into while minimized.
+    if window_size.cmpeq(UVec2::ZERO).any() {
+        return;
+    }
+
+    let scale = (window_size / CANVAS_SIZE).min_element().max(1);
+    let size = CANVAS_SIZE * scale;
+    let offset = window_size.saturating_sub(size) / 2;
+    let scale_factor = window.scale_factor();
+
+    let new_letterbox = Letterbox {
+        scale,
+        origin: offset.as_vec2() / scale_factor,
+        scale_factor,
+    };
+    if *letterbox == new_letterbox {
+        return;
+    }
+    *letterbox = new_letterbox;
+
+    for mut camera in &mut camera_query {
+        camera.viewport = Some(Viewport {
+            physical_position: offset,
+            physical_size: size.min(window_size),
+            ..default()
+        });
+    }
+    // Lay UI out in the canvas area as if it were always `VIEW_SIZE`.
+    ui_scale.0 = scale as f32 / (PIXEL_SIZE * scale_factor);
+}
+
+fn apply_window_mode(
+    settings: Res<Settings>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    let mode = if settings.fullscreen {
+        WindowMode::BorderlessFullscreen
+    } else {
+        WindowMode::Windowed
+    };
+    for mut window in &mut window_query {
+        if window.mode != mode {
+            window.mode = mode;
+        }
+    }
+}